@@ -0,0 +1,662 @@
+use crate::call_graph::CallGraph;
+use crate::el::{locate, parse_template, ExprKind};
+use crate::model::{ConcordDocument, Flow, FlowStep, Location, StepDefinition, TriggerDefinition, Value};
+use crate::visit::{walk_flow_step, Visitor};
+use std::collections::{HashMap, HashSet};
+
+/// Calls `f` for each step, including the steps nested in `error`, `then`/`else`, `switch` cases,
+/// `block`/`try` and `parallel` blocks.
+pub fn for_each_step<'a, F: FnMut(&'a FlowStep)>(steps: &'a [FlowStep], f: &mut F) {
+    struct Steps<'f, F>(&'f mut F);
+
+    impl<'a, F: FnMut(&'a FlowStep)> Visitor<'a> for Steps<'_, F> {
+        fn visit_flow_step(&mut self, step: &'a FlowStep) {
+            (self.0)(step);
+            walk_flow_step(self, step);
+        }
+
+        fn visit_value(&mut self, _: &'a Value) {}
+    }
+
+    let mut visitor = Steps(f);
+    for step in steps {
+        visitor.visit_flow_step(step);
+    }
+}
+
+/// Returns all flows of the document, including the ones defined in profiles.
+pub fn all_flows(document: &ConcordDocument) -> impl Iterator<Item = &Flow> {
+    let profile_flows = document
+        .profiles
+        .iter()
+        .flatten()
+        .flat_map(|profile| profile.flows.iter().flatten());
+    document.flows.iter().flatten().chain(profile_flows)
+}
+
+/// Whether the value is computed at runtime, e.g. `${flowName}` or `flow-${suffix}`.
+pub fn is_expression(value: &str) -> bool {
+    value.contains("${")
+}
+
+/// The edit distance between two strings, in chars: the number of insertions, deletions, substitutions
+/// and transpositions of adjacent chars (the optimal string alignment distance).
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // d[i][j] is the distance between the first i chars of `a` and the first j chars of `b`
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1).min(d[i][j - 1] + 1).min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+/// Returns the candidate most similar to `name`, if it's similar enough to be a likely typo.
+pub fn closest_match<'a, I: IntoIterator<Item = &'a str>>(name: &str, candidates: I) -> Option<&'a str> {
+    let max_distance = (name.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|candidate| {
+            let distance = if candidate.eq_ignore_ascii_case(name) {
+                0
+            } else {
+                edit_distance(name, candidate)
+            };
+            (distance, candidate)
+        })
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+#[derive(Debug)]
+pub struct UndefinedFlow {
+    pub flow_name: String,
+    /// the location of the `call` step
+    pub location: Location,
+    pub suggestion: Option<String>,
+}
+
+/// Checks the `call` steps of the document (including the flows defined in profiles) against
+/// `defined_flows`, usually the names of the flows of all documents in the project.
+/// The calls with expressions in the flow name are skipped.
+pub fn undefined_flows(document: &ConcordDocument, defined_flows: &[&str]) -> Vec<UndefinedFlow> {
+    let mut result = Vec::new();
+    for flow in all_flows(document) {
+        for_each_step(&flow.steps, &mut |step| {
+            let StepDefinition::FlowCall { flow_name, .. } = &step.step else {
+                return;
+            };
+            if is_expression(flow_name) || defined_flows.contains(&flow_name.as_str()) {
+                return;
+            }
+            result.push(UndefinedFlow {
+                flow_name: flow_name.clone(),
+                location: step.location.clone(),
+                suggestion: closest_match(flow_name, defined_flows.iter().copied()).map(str::to_owned),
+            });
+        });
+    }
+    result
+}
+
+/// Flows started by the runtime when a process is cancelled, fails or times out.
+pub const HANDLER_FLOWS: [&str; 3] = ["onCancel", "onFailure", "onTimeout"];
+
+/// Returns the flows that can be started from outside: `publicFlows`, the `configuration.entryPoint`
+/// (`default` if not specified), trigger entry points and the [HANDLER_FLOWS].
+pub fn entry_points(documents: &[&ConcordDocument]) -> Vec<String> {
+    let mut result: Vec<String> = HANDLER_FLOWS.iter().map(|f| f.to_string()).collect();
+    let mut add = |flow: &str| {
+        if !result.iter().any(|f| f == flow) {
+            result.push(flow.to_owned());
+        }
+    };
+    let configurations = documents.iter().flat_map(|document| {
+        let profiles = document.profiles.iter().flatten();
+        document
+            .configuration
+            .iter()
+            .chain(profiles.flat_map(|profile| profile.configuration.iter()))
+    });
+    let mut default_entry_point = true;
+    for configuration in configurations {
+        if let Some(entry_point) = &configuration.entry_point {
+            add(&entry_point.value);
+            default_entry_point = false;
+        }
+    }
+    if default_entry_point {
+        add("default");
+    }
+    for document in documents {
+        for flow in document.public_flows.iter().flatten() {
+            add(&flow.value);
+        }
+        for trigger in document.triggers.iter().flatten() {
+            match &trigger.trigger {
+                TriggerDefinition::GitHub { entry_point, .. }
+                | TriggerDefinition::Cron { entry_point, .. }
+                | TriggerDefinition::Manual { entry_point, .. }
+                | TriggerDefinition::OneOps { entry_point, .. }
+                | TriggerDefinition::Generic { entry_point, .. } => add(entry_point),
+                TriggerDefinition::Unknown { entry_point, .. } => {
+                    if let Some(entry_point) = entry_point {
+                        add(entry_point)
+                    }
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Whether the flow name can be the result of the expression: `${x}` matches anything, `deploy-${env}`
+/// only matches the names starting with `deploy-`.
+pub fn matches_dynamic_name(expression: &str, name: &str) -> bool {
+    // the literal parts between the ${...} expressions
+    let mut parts = Vec::new();
+    let mut rest = expression;
+    while let Some(start) = rest.find("${") {
+        parts.push(&rest[..start]);
+        rest = match rest[start..].find('}') {
+            Some(end) => &rest[start + end + 1..],
+            None => "",
+        };
+    }
+    parts.push(rest);
+
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if !name.starts_with(first) || !name[first.len()..].ends_with(last) {
+        return false;
+    }
+    let mut middle = &name[first.len()..name.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match middle.find(part) {
+            Some(idx) => middle = &middle[idx + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+#[derive(Debug)]
+pub struct UnreachableFlow {
+    /// the index of the document defining the flow
+    pub document: usize,
+    pub name: String,
+    pub location: Location,
+    /// the location of a reachable `call: ${...}` step that might call the flow
+    pub dynamic_call: Option<Location>,
+}
+
+/// Returns the flows that are not reachable from the [entry_points] via `call` steps.
+/// If a flow is only reachable by calls with expressions in the flow name, `dynamic_call` is set:
+/// such flows might be used.
+pub fn unreachable_flows(documents: &[&ConcordDocument]) -> Vec<UnreachableFlow> {
+    let graph = CallGraph::new(documents.iter().copied());
+    let entry_points = entry_points(documents);
+    let roots: Vec<&str> = entry_points.iter().map(String::as_str).collect();
+    let reachable = graph.reachable_from(&roots);
+
+    // flows that might be called dynamically, and the flows they call
+    let mut maybe_reachable: Vec<(&str, &Location)> = Vec::new();
+    for call in graph
+        .dynamic_calls
+        .iter()
+        .filter(|c| reachable.contains(&c.caller.as_str()))
+    {
+        let targets: Vec<&str> = graph
+            .flows
+            .iter()
+            .map(String::as_str)
+            .filter(|flow| matches_dynamic_name(&call.callee, flow))
+            .collect();
+        for flow in graph.reachable_from(&targets) {
+            if !maybe_reachable.iter().any(|(f, _)| *f == flow) {
+                maybe_reachable.push((flow, &call.location));
+            }
+        }
+    }
+
+    let mut result: Vec<UnreachableFlow> = Vec::new();
+    for (idx, document) in documents.iter().enumerate() {
+        for flow in all_flows(document) {
+            if reachable.contains(&flow.name.as_str()) || result.iter().any(|f| f.name == flow.name) {
+                continue;
+            }
+            result.push(UnreachableFlow {
+                document: idx,
+                name: flow.name.clone(),
+                location: flow.location.clone(),
+                dynamic_call: maybe_reachable
+                    .iter()
+                    .find(|(f, _)| *f == flow.name)
+                    .map(|(_, location)| (*location).clone()),
+            });
+        }
+    }
+    result
+}
+
+/// Variables provided by the runtime.
+pub const BUILTIN_VARIABLES: [&str; 10] = [
+    "execution",
+    "context",
+    "txId",
+    "parentInstanceId",
+    "workDir",
+    "initiator",
+    "currentUser",
+    "requestInfo",
+    "processInfo",
+    "projectInfo",
+];
+
+/// Tasks of the standard Concord distribution, available in expressions, e.g. `${crypto.decryptString(s)}`.
+pub const BUILTIN_TASKS: [&str; 16] = [
+    "concord",
+    "crypto",
+    "datetime",
+    "docker",
+    "files",
+    "http",
+    "jsonStore",
+    "kv",
+    "lock",
+    "log",
+    "resource",
+    "sleep",
+    "slack",
+    "smtp",
+    "throw",
+    "uuid",
+];
+
+/// A variable read in an expression.
+#[derive(Debug)]
+pub struct VariableRead {
+    pub name: String,
+    pub location: Location,
+    /// whether the variable is the target of a method call, e.g. `x` in `${x.y()}`, so it may be a task
+    pub method_target: bool,
+}
+
+/// The variables defined and read by the steps of a flow.
+#[derive(Debug, Default)]
+pub struct FlowVariables {
+    /// set by `set`, `out`, form calls and the `in` of `call` steps (for the called flow)
+    pub defined: Vec<String>,
+    pub reads: Vec<VariableRead>,
+    /// the names of the tasks called by `task` steps
+    pub tasks: Vec<String>,
+}
+
+/// Returns the first segment of a variable name, e.g. `x` for `x.y` in `set`.
+fn root_variable(name: &str) -> &str {
+    name.split('.').next().unwrap_or(name).trim()
+}
+
+impl FlowVariables {
+    /// Collects the variables of the steps. `source` is the YAML source of the document, used to compute
+    /// the exact locations of the expressions.
+    pub fn new(steps: &[FlowStep], source: &str) -> Self {
+        let mut result = FlowVariables::default();
+        result.steps(steps, source, &[]);
+        result
+    }
+
+    fn define(&mut self, name: &str) {
+        let name = root_variable(name);
+        if !name.is_empty() && !is_expression(name) && !self.defined.iter().any(|d| d == name) {
+            self.defined.push(name.to_owned());
+        }
+    }
+
+    /// Records the variables read by the expressions in the string. `implicit` are the variables available in
+    /// the context of the string, e.g. `item` in loops.
+    fn read_string(&mut self, value: &str, location: &Location, source: &str, implicit: &[&str]) {
+        if !is_expression(value) {
+            return;
+        }
+        // malformed expressions are reported elsewhere
+        let Ok(template) = parse_template(value, &locate(source, location, value)) else {
+            return;
+        };
+        for expr in template.expressions() {
+            // `${hasVariable('x') ? x : 'default'}` is fine
+            let mut checked = Vec::new();
+            // `${task.method()}` may call a task, which isn't a variable
+            let mut method_targets = Vec::new();
+            expr.walk(&mut |e| match &e.kind {
+                ExprKind::Call { target, args } => {
+                    let function = match &target.kind {
+                        ExprKind::Identifier(function) => function.as_str(),
+                        _ => "",
+                    };
+                    if let ("hasVariable" | "hasNonNullVariable", Some(ExprKind::String(name))) =
+                        (function, args.first().map(|arg| &arg.kind))
+                    {
+                        checked.push(root_variable(name));
+                    }
+                }
+                ExprKind::MethodCall { target, .. } if matches!(target.kind, ExprKind::Identifier(_)) => {
+                    method_targets.push(target.as_ref());
+                }
+                _ => {}
+            });
+            for variable in expr.free_variables() {
+                let ExprKind::Identifier(name) = &variable.kind else {
+                    continue;
+                };
+                if implicit.contains(&name.as_str()) || checked.contains(&name.as_str()) {
+                    continue;
+                }
+                self.reads.push(VariableRead {
+                    name: name.clone(),
+                    location: variable.location.clone(),
+                    method_target: method_targets
+                        .iter()
+                        .any(|target| std::ptr::eq(*target, variable)),
+                });
+            }
+        }
+    }
+
+    fn read_value(&mut self, value: &Value, location: &Location, source: &str, implicit: &[&str]) {
+        match value {
+            Value::String(s) => self.read_string(s, location, source, implicit),
+            Value::Array(items) => {
+                for item in items {
+                    self.read_value(item, location, source, implicit);
+                }
+            }
+            Value::Mapping(kvs) => {
+                for kv in kvs {
+                    self.read_value(&kv.value, &kv.location, source, implicit);
+                }
+            }
+            Value::Boolean(_) | Value::Float(_) | Value::Integer(_) => {}
+        }
+    }
+
+    /// `out: x`, `out: [x, y]` or `out: {x: "${result.y}"}`.
+    fn output(&mut self, output: &Value, location: &Location, source: &str, implicit: &[&str]) {
+        match output {
+            Value::String(name) => self.define(name),
+            Value::Array(names) => {
+                for name in names {
+                    if let Value::String(name) = name {
+                        self.define(name);
+                    }
+                }
+            }
+            Value::Mapping(kvs) => {
+                let mut implicit = implicit.to_vec();
+                implicit.push("result");
+                for kv in kvs {
+                    self.define(&kv.key);
+                    self.read_value(&kv.value, &kv.location, source, &implicit);
+                }
+            }
+            Value::Boolean(_) | Value::Float(_) | Value::Integer(_) => {
+                self.read_value(output, location, source, implicit)
+            }
+        }
+    }
+
+    fn error_steps(&mut self, error: &Option<Vec<FlowStep>>, source: &str, implicit: &[&str]) {
+        if let Some(error) = error {
+            let mut implicit = implicit.to_vec();
+            implicit.push("lastError");
+            self.steps(error, source, &implicit);
+        }
+    }
+
+    fn steps(&mut self, steps: &[FlowStep], source: &str, implicit: &[&str]) {
+        for step in steps {
+            self.step(step, source, implicit);
+        }
+    }
+
+    fn step(&mut self, step: &FlowStep, source: &str, implicit: &[&str]) {
+        let location = &step.location;
+        let mut implicit = implicit.to_vec();
+        let looping = match &step.step {
+            StepDefinition::TaskCall { looping, .. }
+            | StepDefinition::Script { looping, .. }
+            | StepDefinition::FlowCall { looping, .. }
+            | StepDefinition::Block { looping, .. } => looping.as_ref(),
+            _ => None,
+        };
+        if let Some(looping) = looping {
+            self.read_value(&looping.items, &looping.location, source, &implicit);
+            implicit.extend(["item", "itemIndex"]);
+        }
+        let implicit = implicit.as_slice();
+
+        if let StepDefinition::TaskCall { task_name, .. } = &step.step {
+            if !is_expression(task_name) && !self.tasks.contains(task_name) {
+                self.tasks.push(task_name.clone());
+            }
+        }
+
+        match &step.step {
+            StepDefinition::TaskCall {
+                input, output, error, ..
+            }
+            | StepDefinition::Script {
+                input, output, error, ..
+            } => {
+                if let Some(input) = input {
+                    self.read_value(input, location, source, implicit);
+                }
+                if let Some(output) = output {
+                    self.output(output, location, source, implicit);
+                }
+                self.error_steps(error, source, implicit);
+            }
+            StepDefinition::Expression {
+                expr, output, error, ..
+            } => {
+                self.read_string(expr, location, source, implicit);
+                if let Some(output) = output {
+                    self.output(output, location, source, implicit);
+                }
+                self.error_steps(error, source, implicit);
+            }
+            StepDefinition::FlowCall {
+                flow_name,
+                input,
+                output,
+                error,
+                ..
+            } => {
+                self.read_string(flow_name, location, source, implicit);
+                if let Some(input) = input {
+                    self.read_value(input, location, source, implicit);
+                    // the input is available to the called flow
+                    if let Value::Mapping(kvs) = input {
+                        kvs.iter().for_each(|kv| self.define(&kv.key));
+                    }
+                }
+                if let Some(output) = output {
+                    self.output(output, location, source, implicit);
+                }
+                self.error_steps(error, source, implicit);
+            }
+            StepDefinition::If {
+                expression,
+                then_steps,
+                else_steps,
+                ..
+            } => {
+                self.read_string(expression, location, source, implicit);
+                self.steps(then_steps, source, implicit);
+                if let Some(else_steps) = else_steps {
+                    self.steps(else_steps, source, implicit);
+                }
+            }
+            StepDefinition::SetVariables { vars, .. } => {
+                for kv in vars {
+                    self.define(&kv.key);
+                    self.read_value(&kv.value, &kv.location, source, implicit);
+                }
+            }
+            StepDefinition::ParallelBlock { steps, output, .. } => {
+                self.steps(steps, source, implicit);
+                if let Some(output) = output {
+                    self.output(output, location, source, implicit);
+                }
+            }
+            StepDefinition::Block {
+                steps, output, error, ..
+            } => {
+                self.steps(steps, source, implicit);
+                if let Some(output) = output {
+                    self.output(output, location, source, implicit);
+                }
+                self.error_steps(error, source, implicit);
+            }
+            StepDefinition::Switch {
+                expression,
+                cases,
+                default,
+                ..
+            } => {
+                self.read_string(expression, location, source, implicit);
+                for case in cases {
+                    self.read_value(&case.label, location, source, implicit);
+                    self.steps(&case.steps, source, implicit);
+                }
+                if let Some(default) = default {
+                    self.steps(default, source, implicit);
+                }
+            }
+            StepDefinition::FormCall {
+                form_name,
+                run_as,
+                values,
+                ..
+            } => {
+                self.define(form_name);
+                for value in [run_as, values].into_iter().flatten() {
+                    self.read_value(value, location, source, implicit);
+                }
+            }
+            StepDefinition::Checkpoint { .. } | StepDefinition::Suspend { .. } | StepDefinition::Return => {}
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct UndefinedVariable {
+    /// the index of the document
+    pub document: usize,
+    pub flow: String,
+    pub name: String,
+    /// the location of the variable in the expression
+    pub location: Location,
+    pub suggestion: Option<String>,
+}
+
+/// Returns the variables read in the flows but never defined: not in `configuration.arguments`, not a
+/// [BUILTIN_VARIABLES] and not set by a flow that runs in the same process as the flow, i.e. by a flow
+/// reachable from the flow or from any of its callers. Method calls on [BUILTIN_TASKS] and on the tasks
+/// called by `task` steps, e.g. `${http.get(url)}`, aren't variable reads.
+///
+/// `documents` are pairs of the YAML source of a document and the document parsed from it, the source is
+/// used to locate the variables in the expressions.
+///
+/// Variables passed when the process is started, variables set by scripts or tasks and the tasks of plugins
+/// used only in expressions can't be known, so the result may contain false positives.
+pub fn undefined_variables(documents: &[(&str, &ConcordDocument)]) -> Vec<UndefinedVariable> {
+    let graph = CallGraph::new(documents.iter().map(|(_, document)| *document));
+
+    let mut arguments: Vec<&str> = BUILTIN_VARIABLES.to_vec();
+    for (_, document) in documents {
+        let profiles = document.profiles.iter().flatten();
+        let configurations = document
+            .configuration
+            .iter()
+            .chain(profiles.flat_map(|profile| profile.configuration.iter()));
+        for configuration in configurations {
+            for kv in configuration.arguments.iter().flat_map(|a| &a.value) {
+                arguments.push(&kv.key);
+            }
+        }
+    }
+
+    let mut flows = Vec::new();
+    for (idx, (source, document)) in documents.iter().enumerate() {
+        for flow in all_flows(document) {
+            flows.push((idx, flow, FlowVariables::new(&flow.steps, source)));
+        }
+    }
+
+    // the variables set by each flow, a flow can be defined in several documents
+    let mut defined_by: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (_, flow, variables) in &flows {
+        let defined = defined_by.entry(flow.name.as_str()).or_default();
+        defined.extend(variables.defined.iter().map(String::as_str));
+    }
+
+    let mut tasks: HashSet<&str> = BUILTIN_TASKS.into_iter().collect();
+    tasks.extend(
+        flows
+            .iter()
+            .flat_map(|(_, _, variables)| variables.tasks.iter().map(String::as_str)),
+    );
+
+    let mut result = Vec::new();
+    for (idx, flow, variables) in &flows {
+        // the flows running in the same process: everything reachable from the flow and its callers
+        let mut ancestors = vec![flow.name.as_str()];
+        let mut visited: HashSet<&str> = ancestors.iter().copied().collect();
+        let mut queue = ancestors.clone();
+        while let Some(name) = queue.pop() {
+            for caller in graph.callers(name) {
+                if visited.insert(caller) {
+                    ancestors.push(caller);
+                    queue.push(caller);
+                }
+            }
+        }
+        let related = graph.reachable_from(&ancestors);
+
+        let mut defined: Vec<&str> = arguments.clone();
+        for name in &related {
+            defined.extend(defined_by.get(name).into_iter().flatten());
+        }
+        let lookup: HashSet<&str> = defined.iter().copied().collect();
+
+        for read in &variables.reads {
+            if lookup.contains(read.name.as_str())
+                || (read.method_target && tasks.contains(read.name.as_str()))
+            {
+                continue;
+            }
+            result.push(UndefinedVariable {
+                document: *idx,
+                flow: flow.name.clone(),
+                name: read.name.clone(),
+                location: read.location.clone(),
+                suggestion: closest_match(&read.name, defined.iter().copied()).map(str::to_owned),
+            });
+        }
+    }
+    result
+}
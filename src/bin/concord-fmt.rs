@@ -0,0 +1,139 @@
+//! Formats Concord YAML files.
+//!
+//! ```text
+//! concord-fmt [--check] [FILE]...
+//! ```
+//!
+//! Files are rewritten in place. Without files, reads the standard input and writes to the standard output.
+//! With `--check`, nothing is written and the exit code is 1 if any of the files isn't formatted.
+//! Errors (invalid files, I/O errors) result in the exit code 2. The formatted text is parsed again and files whose
+//! contents would change are left as they are, with the exit code 2.
+
+use concord_v2_parser::diagnostic::Diagnostic;
+use concord_v2_parser::emitter::emit_stream;
+use concord_v2_parser::error::ParseError;
+use concord_v2_parser::formatter;
+use concord_v2_parser::input::Input;
+use concord_v2_parser::parser::parse_stream;
+use std::io::IsTerminal;
+use std::process::ExitCode;
+
+const USAGE: &str = "Usage: concord-fmt [--check] [FILE]...";
+
+/// Renders the error of reading, decoding or parsing the file.
+fn render(src: &str, e: &ParseError, file_name: &str, color: bool) -> String {
+    Diagnostic::new(src, e)
+        .with_file_name(file_name)
+        .with_color(color)
+        .to_string()
+}
+
+/// Returns the formatted text of the input or a rendered error.
+fn format<T: Iterator<Item = char>>(
+    mut input: Input<T>,
+    file_name: &str,
+    color: bool,
+) -> Result<String, String> {
+    let src = input.text().clone();
+    let render = |e| render(&src, &e, file_name, color);
+
+    // only valid Concord files are formatted
+    let documents = parse_stream(&mut input).map_err(render)?;
+
+    let formatted = formatter::format(&src).map_err(render)?;
+
+    // never write a file whose meaning changed, the emitter's output doesn't depend on locations
+    let unchanged = Input::try_from(formatted.as_str())
+        .and_then(|mut input| parse_stream(&mut input))
+        .is_ok_and(|formatted| emit_stream(&formatted) == emit_stream(&documents));
+    if !unchanged {
+        return Err(format!(
+            "error: formatting {file_name} would change its contents, it was left unchanged\n"
+        ));
+    }
+    Ok(formatted)
+}
+
+fn main() -> ExitCode {
+    let mut check = false;
+    let mut files = Vec::new();
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--check" => check = true,
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            arg if arg.starts_with('-') => {
+                eprintln!("Unknown option '{arg}'\n{USAGE}");
+                return ExitCode::from(2);
+            }
+            _ => files.push(arg),
+        }
+    }
+
+    let color = std::io::stderr().is_terminal();
+
+    if files.is_empty() {
+        let input = match Input::from_reader(std::io::stdin()) {
+            Ok(input) => input,
+            Err(e) => {
+                eprint!("{}", render("", &e, "<stdin>", color));
+                return ExitCode::from(2);
+            }
+        };
+        let src = input.text().clone();
+        return match format(input, "<stdin>", color) {
+            Ok(formatted) if check && *formatted != *src => ExitCode::from(1),
+            Ok(_) if check => ExitCode::SUCCESS,
+            Ok(formatted) => {
+                print!("{formatted}");
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprint!("{e}");
+                ExitCode::from(2)
+            }
+        };
+    }
+
+    let mut failed = false;
+    let mut unformatted = false;
+    for file in &files {
+        let input = match Input::from_path(file) {
+            Ok(input) => input,
+            Err(e) => {
+                eprint!("{}", render("", &e, file, color));
+                failed = true;
+                continue;
+            }
+        };
+        let src = input.text().clone();
+        let formatted = match format(input, file, color) {
+            Ok(formatted) => formatted,
+            Err(e) => {
+                eprint!("{e}");
+                failed = true;
+                continue;
+            }
+        };
+        if *formatted == *src {
+            continue;
+        }
+        if check {
+            println!("{file}");
+            unformatted = true;
+        } else if let Err(e) = std::fs::write(file, formatted) {
+            eprintln!("error: can't write {file}: {e}");
+            failed = true;
+        }
+    }
+
+    if failed {
+        ExitCode::from(2)
+    } else if unformatted {
+        ExitCode::from(1)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
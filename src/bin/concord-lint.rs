@@ -0,0 +1,206 @@
+//! Checks Concord YAML files.
+//!
+//! ```text
+//! concord-lint [--format text|json|sarif] [--enable RULE]... [--disable RULE]... [--deny-warnings] FILE...
+//! concord-lint --list-rules
+//! ```
+//!
+//! All files are checked together, so flows defined in one file can be used in another.
+//! The exit code is 1 if there are errors (or warnings, with `--deny-warnings`) and 2 if the files
+//! can't be read or the arguments are invalid.
+
+use concord_v2_parser::diagnostic::{Diagnostic, Severity};
+use concord_v2_parser::error::ErrorKind;
+use concord_v2_parser::input::Input;
+use concord_v2_parser::lint::{self, Lint, LintDocument, LintRule};
+use concord_v2_parser::parser::parse_stream_recovering;
+use std::io::IsTerminal;
+use std::process::ExitCode;
+use std::sync::Arc;
+
+const USAGE: &str = "Usage: concord-lint [--format text|json|sarif] [--enable RULE]... [--disable RULE]... \
+                     [--deny-warnings] FILE...\n       concord-lint --list-rules";
+
+enum Format {
+    Text,
+    Json,
+    Sarif,
+}
+
+struct Args {
+    format: Format,
+    enable: Vec<String>,
+    disable: Vec<String>,
+    deny_warnings: bool,
+    list_rules: bool,
+    files: Vec<String>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        format: Format::Text,
+        enable: Vec::new(),
+        disable: Vec::new(),
+        deny_warnings: false,
+        list_rules: false,
+        files: Vec::new(),
+    };
+    let mut iter = std::env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        // --option=value is the same as --option value
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name.to_owned(), Some(value.to_owned())),
+            _ => (arg.clone(), None),
+        };
+        let mut value = || {
+            inline_value
+                .clone()
+                .or_else(|| iter.next())
+                .ok_or_else(|| format!("Missing value for '{name}'"))
+        };
+        match name.as_str() {
+            "--format" => {
+                args.format = match value()?.as_str() {
+                    "text" => Format::Text,
+                    "json" => Format::Json,
+                    "sarif" => Format::Sarif,
+                    format => return Err(format!("Unknown format '{format}'")),
+                }
+            }
+            "--enable" => args.enable.push(value()?),
+            "--disable" => args.disable.push(value()?),
+            "--deny-warnings" => args.deny_warnings = true,
+            "--list-rules" => args.list_rules = true,
+            name if name.starts_with('-') => return Err(format!("Unknown option '{name}'")),
+            _ => args.files.push(arg),
+        }
+    }
+    if args.files.is_empty() && !args.list_rules {
+        return Err("No files to check".to_owned());
+    }
+    Ok(args)
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{e}\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let all_rules = lint::rules();
+    if args.list_rules {
+        for rule in &all_rules {
+            let default = if rule.enabled_by_default() {
+                ""
+            } else {
+                " (disabled by default)"
+            };
+            println!("{}: {}{default}", rule.name(), rule.description());
+        }
+        return ExitCode::SUCCESS;
+    }
+
+    for name in args.enable.iter().chain(&args.disable) {
+        if !all_rules.iter().any(|rule| rule.name() == name) {
+            eprintln!("Unknown rule '{name}', see --list-rules");
+            return ExitCode::from(2);
+        }
+    }
+    let rules: Vec<&dyn LintRule> = all_rules
+        .iter()
+        .filter(|rule| {
+            let name = &rule.name().to_owned();
+            !args.disable.contains(name) && (rule.enabled_by_default() || args.enable.contains(name))
+        })
+        .map(|rule| rule.as_ref())
+        .collect();
+
+    let mut inputs = Vec::new();
+    for file in &args.files {
+        match Input::from_path(file) {
+            Err(e) if matches!(e.kind, ErrorKind::Io) => {
+                eprintln!("error: {}", e.msg);
+                return ExitCode::from(2);
+            }
+            input => inputs.push(input),
+        }
+    }
+    // the text of each file, empty if it isn't valid UTF-8
+    let sources: Vec<Arc<str>> = inputs
+        .iter()
+        .map(|input| {
+            input
+                .as_ref()
+                .map_or_else(|_| Arc::from(""), |input| input.text().clone())
+        })
+        .collect();
+
+    let mut lints = Vec::new();
+    let mut parsed = Vec::new();
+    for ((file, src), input) in args.files.iter().zip(&sources).zip(inputs) {
+        let mut input = match input {
+            Ok(input) => input.with_source_id(file.as_str()),
+            Err(e) => {
+                lints.push(Lint::from_parse_error(file, e));
+                continue;
+            }
+        };
+        let (documents, errors) = parse_stream_recovering(&mut input);
+        lints.extend(errors.into_iter().map(|e| Lint::from_parse_error(file, e)));
+        parsed.push((file, src, documents));
+    }
+    let documents: Vec<LintDocument> = parsed
+        .iter()
+        .flat_map(|(file, source, documents)| {
+            documents.iter().map(|document| LintDocument {
+                file,
+                source,
+                document,
+            })
+        })
+        .collect();
+    lints.extend(lint::run(&rules, &documents));
+    lints.sort_by_key(|lint| {
+        let start = lint.location.as_ref().map(|l| l.start.index);
+        (lint.file.clone(), start)
+    });
+
+    match args.format {
+        Format::Text => {
+            let color = std::io::stdout().is_terminal();
+            for lint in &lints {
+                let src = args
+                    .files
+                    .iter()
+                    .position(|file| *file == lint.file)
+                    .map(|idx| &*sources[idx])
+                    .unwrap_or_default();
+                let mut diagnostic = Diagnostic::from_message(src, &lint.msg, lint.location.as_ref())
+                    .with_file_name(&lint.file)
+                    .with_severity(lint.severity)
+                    .with_code(lint.rule)
+                    .with_color(color);
+                if let Some(help) = &lint.help {
+                    diagnostic = diagnostic.with_help(help);
+                }
+                println!("{diagnostic}");
+            }
+        }
+        Format::Json => print!("{}", lint::to_json(&lints)),
+        Format::Sarif => print!("{}", lint::to_sarif(&lints, &rules)),
+    }
+
+    let count = |severity| lints.iter().filter(|lint| lint.severity == severity).count();
+    let (errors, warnings) = (count(Severity::Error), count(Severity::Warning));
+    if let Format::Text = args.format {
+        eprintln!("{errors} error(s), {warnings} warning(s)");
+    }
+    if errors > 0 || (args.deny_warnings && warnings > 0) {
+        ExitCode::from(1)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
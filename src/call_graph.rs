@@ -0,0 +1,365 @@
+use crate::analysis::{all_flows, for_each_step, is_expression};
+use crate::model::{ConcordDocument, Location, StepDefinition};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+#[derive(Debug)]
+pub struct Call {
+    pub caller: String,
+    /// the name of the called flow, or an expression for the dynamic calls
+    pub callee: String,
+    /// the location of the `call` step
+    pub location: Location,
+}
+
+/// The graph of `call` steps between flows. The queries use the adjacency lists built by [CallGraph::new],
+/// so they don't reflect the changes of the fields made afterwards.
+#[derive(Debug, Default)]
+pub struct CallGraph {
+    /// the defined flows, in the order of definition
+    pub flows: Vec<String>,
+    pub public_flows: Vec<String>,
+    /// the calls with known flow names, including the calls of undefined flows
+    pub calls: Vec<Call>,
+    /// the calls with expressions in the flow name, e.g. `call: ${flowName}`
+    pub dynamic_calls: Vec<Call>,
+    adjacency: Adjacency,
+}
+
+/// The nodes and the unique edges of the graph, with the nodes identified by their indexes in `nodes`.
+#[derive(Debug, Default)]
+struct Adjacency {
+    /// the defined flows followed by the undefined ones that are called
+    nodes: Vec<String>,
+    ids: HashMap<String, usize>,
+    /// the unique caller/callee pairs in the order of the calls
+    edges: Vec<(usize, usize)>,
+    callees: Vec<Vec<usize>>,
+    callers: Vec<Vec<usize>>,
+}
+
+impl Adjacency {
+    fn new(flows: &[String], calls: &[Call]) -> Self {
+        let mut adjacency = Adjacency::default();
+        let names = flows.iter().chain(calls.iter().map(|call| &call.callee));
+        for name in names.chain(calls.iter().map(|call| &call.caller)) {
+            if !adjacency.ids.contains_key(name) {
+                adjacency.ids.insert(name.clone(), adjacency.nodes.len());
+                adjacency.nodes.push(name.clone());
+            }
+        }
+        adjacency.callees = vec![Vec::new(); adjacency.nodes.len()];
+        adjacency.callers = vec![Vec::new(); adjacency.nodes.len()];
+        let mut seen = HashSet::new();
+        for call in calls {
+            let edge = (adjacency.ids[&call.caller], adjacency.ids[&call.callee]);
+            if seen.insert(edge) {
+                adjacency.edges.push(edge);
+                adjacency.callees[edge.0].push(edge.1);
+                adjacency.callers[edge.1].push(edge.0);
+            }
+        }
+        adjacency
+    }
+
+    fn names(&self, ids: &[usize]) -> Vec<&str> {
+        ids.iter().map(|id| self.nodes[*id].as_str()).collect()
+    }
+}
+
+impl CallGraph {
+    /// Builds the graph from all flows of the documents, including the flows defined in profiles.
+    pub fn new<'a, I: IntoIterator<Item = &'a ConcordDocument>>(documents: I) -> Self {
+        let mut graph = CallGraph::default();
+        let mut defined = HashSet::new();
+        for document in documents {
+            graph
+                .public_flows
+                .extend(document.public_flows.iter().flatten().map(|f| f.value.clone()));
+            for flow in all_flows(document) {
+                if defined.insert(flow.name.as_str()) {
+                    graph.flows.push(flow.name.clone());
+                }
+                for_each_step(&flow.steps, &mut |step| {
+                    if let StepDefinition::FlowCall { flow_name, .. } = &step.step {
+                        let call = Call {
+                            caller: flow.name.clone(),
+                            callee: flow_name.clone(),
+                            location: step.location.clone(),
+                        };
+                        if is_expression(flow_name) {
+                            graph.dynamic_calls.push(call);
+                        } else {
+                            graph.calls.push(call);
+                        }
+                    }
+                });
+            }
+        }
+        graph.adjacency = Adjacency::new(&graph.flows, &graph.calls);
+        graph
+    }
+
+    pub fn is_defined(&self, flow: &str) -> bool {
+        // the defined flows are the first nodes
+        self.adjacency
+            .ids
+            .get(flow)
+            .is_some_and(|id| *id < self.flows.len())
+    }
+
+    pub fn is_public(&self, flow: &str) -> bool {
+        self.public_flows.iter().any(|f| f == flow)
+    }
+
+    /// Returns the flows called by `flow`, without duplicates.
+    pub fn callees(&self, flow: &str) -> Vec<&str> {
+        match self.adjacency.ids.get(flow) {
+            Some(id) => self.adjacency.names(&self.adjacency.callees[*id]),
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns the flows that call `flow`, without duplicates.
+    pub fn callers(&self, flow: &str) -> Vec<&str> {
+        match self.adjacency.ids.get(flow) {
+            Some(id) => self.adjacency.names(&self.adjacency.callers[*id]),
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns the flows reachable from `roots` via static calls, including the roots themselves.
+    pub fn reachable_from<'a>(&'a self, roots: &[&'a str]) -> Vec<&'a str> {
+        let mut result: Vec<&str> = Vec::new();
+        let mut visited = HashSet::new();
+        let mut queue: Vec<&str> = roots.to_vec();
+        while let Some(flow) = queue.pop() {
+            if !visited.insert(flow) {
+                continue;
+            }
+            result.push(flow);
+            queue.extend(self.callees(flow));
+        }
+        result
+    }
+
+    /// Returns the names of all nodes: the defined flows followed by the undefined ones that are called.
+    fn nodes(&self) -> Vec<&str> {
+        self.adjacency.nodes.iter().map(String::as_str).collect()
+    }
+
+    /// Returns the unique caller/callee pairs in the order of the calls.
+    fn edges(&self) -> impl Iterator<Item = (&str, &str)> {
+        let nodes = &self.adjacency.nodes;
+        self.adjacency
+            .edges
+            .iter()
+            .map(|(caller, callee)| (nodes[*caller].as_str(), nodes[*callee].as_str()))
+    }
+
+    /// Returns the groups of mutually recursive flows (the strongly connected components with more than
+    /// one flow or with a flow calling itself).
+    pub fn cycles(&self) -> Vec<Vec<String>> {
+        let nodes = &self.adjacency.nodes;
+        let successors = &self.adjacency.callees;
+
+        let mut tarjan = Tarjan {
+            successors,
+            index: vec![None; nodes.len()],
+            low_link: vec![0; nodes.len()],
+            on_stack: vec![false; nodes.len()],
+            stack: Vec::new(),
+            next_index: 0,
+            components: Vec::new(),
+        };
+        for node in 0..nodes.len() {
+            if tarjan.index[node].is_none() {
+                tarjan.visit(node);
+            }
+        }
+
+        let mut cycles: Vec<Vec<String>> = tarjan
+            .components
+            .into_iter()
+            .filter(|c| c.len() > 1 || successors[c[0]].contains(&c[0]))
+            .map(|c| {
+                // keep the order of definition
+                let c: BTreeSet<usize> = c.into_iter().collect();
+                c.into_iter().map(|i| nodes[i].to_owned()).collect()
+            })
+            .collect();
+        cycles.sort();
+        cycles
+    }
+
+    /// Maps the flows of the cycles to the indexes of the cycles.
+    fn cycle_members(cycles: &[Vec<String>]) -> HashMap<&str, usize> {
+        let mut members = HashMap::new();
+        for (idx, cycle) in cycles.iter().enumerate() {
+            members.extend(cycle.iter().map(|f| (f.as_str(), idx)));
+        }
+        members
+    }
+
+    fn in_cycle(members: &HashMap<&str, usize>, caller: &str, callee: &str) -> bool {
+        matches!((members.get(caller), members.get(callee)), (Some(a), Some(b)) if a == b)
+    }
+
+    /// Renders the graph in the Graphviz DOT format. Public flows are drawn in bold, undefined flows are
+    /// dashed, recursive calls are red.
+    pub fn to_dot(&self) -> String {
+        let cycles = self.cycles();
+        let members = Self::cycle_members(&cycles);
+        let mut result = String::from("digraph flows {\n  node [shape=box];\n");
+        for node in self.nodes() {
+            let mut styles = Vec::new();
+            if self.is_public(node) {
+                styles.push("bold");
+            }
+            if !self.is_defined(node) {
+                styles.push("dashed");
+            }
+            let mut attributes = Vec::new();
+            if !styles.is_empty() {
+                attributes.push(format!("style=\"{}\"", styles.join(",")));
+            }
+            if members.contains_key(node) {
+                attributes.push("color=red".to_owned());
+            }
+            if attributes.is_empty() {
+                result.push_str(&format!("  {};\n", dot_id(node)));
+            } else {
+                result.push_str(&format!("  {} [{}];\n", dot_id(node), attributes.join(", ")));
+            }
+        }
+        for (caller, callee) in self.edges() {
+            let attributes = if Self::in_cycle(&members, caller, callee) {
+                " [color=red]"
+            } else {
+                ""
+            };
+            result.push_str(&format!(
+                "  {} -> {}{attributes};\n",
+                dot_id(caller),
+                dot_id(callee)
+            ));
+        }
+        result.push_str("}\n");
+        result
+    }
+
+    /// Renders the graph as a Mermaid flowchart, with the same conventions as [CallGraph::to_dot].
+    pub fn to_mermaid(&self) -> String {
+        let cycles = self.cycles();
+        let members = Self::cycle_members(&cycles);
+        let nodes = self.nodes();
+        let id = |node: &str| format!("f{}", self.adjacency.ids.get(node).copied().unwrap_or(0));
+
+        let mut result = String::from("flowchart LR\n");
+        for node in &nodes {
+            result.push_str(&format!("  {}[\"{}\"]\n", id(node), node.replace('"', "#quot;")));
+        }
+        let mut recursive_links = Vec::new();
+        for (i, (caller, callee)) in self.edges().enumerate() {
+            result.push_str(&format!("  {} --> {}\n", id(caller), id(callee)));
+            if Self::in_cycle(&members, caller, callee) {
+                recursive_links.push(i.to_string());
+            }
+        }
+
+        let class = |result: &mut String, name: &str, style: &str, members: Vec<String>| {
+            if !members.is_empty() {
+                result.push_str(&format!("  classDef {name} {style}\n"));
+                result.push_str(&format!("  class {} {name}\n", members.join(",")));
+            }
+        };
+        let public = nodes
+            .iter()
+            .filter(|n| self.is_public(n))
+            .map(|n| id(n))
+            .collect();
+        class(&mut result, "public", "stroke-width:3px", public);
+        let undefined = nodes
+            .iter()
+            .filter(|n| !self.is_defined(n))
+            .map(|n| id(n))
+            .collect();
+        class(&mut result, "undefined", "stroke-dasharray:5 5", undefined);
+        let recursive = nodes
+            .iter()
+            .filter(|n| members.contains_key(*n))
+            .map(|n| id(n))
+            .collect();
+        class(&mut result, "recursive", "stroke:#f00", recursive);
+        if !recursive_links.is_empty() {
+            result.push_str(&format!(
+                "  linkStyle {} stroke:#f00\n",
+                recursive_links.join(",")
+            ));
+        }
+        result
+    }
+}
+
+fn dot_id(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+struct Tarjan<'a> {
+    successors: &'a [Vec<usize>],
+    index: Vec<Option<usize>>,
+    low_link: Vec<usize>,
+    on_stack: Vec<bool>,
+    stack: Vec<usize>,
+    next_index: usize,
+    components: Vec<Vec<usize>>,
+}
+
+impl Tarjan<'_> {
+    /// Visits the nodes reachable from `root` depth-first. The path is kept on an explicit stack of nodes and
+    /// the positions of their next successors, long call chains would overflow the thread's stack otherwise.
+    fn visit(&mut self, root: usize) {
+        self.enter(root);
+        let mut path = vec![(root, 0)];
+        while let Some((node, next)) = path.last_mut() {
+            let node = *node;
+            if let Some(&successor) = self.successors[node].get(*next) {
+                *next += 1;
+                match self.index[successor] {
+                    None => {
+                        self.enter(successor);
+                        path.push((successor, 0));
+                    }
+                    Some(index) if self.on_stack[successor] => {
+                        self.low_link[node] = self.low_link[node].min(index);
+                    }
+                    Some(_) => {}
+                }
+                continue;
+            }
+
+            path.pop();
+            if let Some((parent, _)) = path.last() {
+                self.low_link[*parent] = self.low_link[*parent].min(self.low_link[node]);
+            }
+            if Some(self.low_link[node]) == self.index[node] {
+                let mut component = Vec::new();
+                while let Some(member) = self.stack.pop() {
+                    self.on_stack[member] = false;
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                self.components.push(component);
+            }
+        }
+    }
+
+    fn enter(&mut self, node: usize) {
+        self.index[node] = Some(self.next_index);
+        self.low_link[node] = self.next_index;
+        self.next_index += 1;
+        self.stack.push(node);
+        self.on_stack[node] = true;
+    }
+}
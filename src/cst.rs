@@ -0,0 +1,447 @@
+use crate::error::{ErrorKind, ParseError};
+use crate::input::{Event, Input, Marker};
+use crate::model::Position;
+use std::fmt::{Display, Formatter};
+use yaml_rust2::scanner::TScalarStyle;
+
+/// A lossless concrete syntax tree of a YAML stream.
+///
+/// Unlike the model produced by [crate::parser], the tree keeps everything found in the source text: comments,
+/// whitespace, indicators (`-`, `:`, `[`, etc.) and the original representation of scalars. Printing the tree
+/// (with [Display]) reproduces the source byte-for-byte, so a tool can modify some nodes and write the file
+/// back without touching the rest of it.
+///
+/// Anchors and tags are kept as tokens preceding the node they belong to.
+///
+/// ```
+/// use concord_v2_parser::cst;
+///
+/// let src = "flows:\n  main:\n    - log: 'Hi!' # greet\n";
+/// let mut stream = cst::parse(src).unwrap();
+/// assert_eq!(stream.to_string(), src);
+///
+/// let log = stream.documents_mut().next().unwrap().root_mut().unwrap()
+///     .get_mut("flows").unwrap()
+///     .get_mut("main").unwrap()
+///     .items_mut().next().unwrap()
+///     .get_mut("log").unwrap();
+/// log.set_scalar("\"Hello!\"");
+/// assert_eq!(stream.to_string(), "flows:\n  main:\n    - log: \"Hello!\" # greet\n");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    pub kind: NodeKind,
+    pub start: Position,
+    pub end: Position,
+    pub children: Vec<Element>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeKind {
+    Stream,
+    Document,
+    Mapping { flow: bool },
+    Sequence { flow: bool },
+    Scalar { style: ScalarStyle, value: String },
+    Alias,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScalarStyle {
+    Plain,
+    SingleQuoted,
+    DoubleQuoted,
+    Literal,
+    Folded,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Element {
+    Node(Node),
+    Token(Token),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Whitespace,
+    Newline,
+    Comment,
+    /// `-`, `?`, `:`, `,` and brackets
+    Indicator,
+    /// `---` and `...`
+    DocumentMarker,
+    Directive,
+    Anchor,
+    Tag,
+    /// the source text of a scalar or an alias
+    Text,
+    Other,
+}
+
+impl Node {
+    /// Returns the child nodes, skipping the trivia.
+    pub fn nodes(&self) -> impl Iterator<Item = &Node> {
+        self.children.iter().filter_map(|element| match element {
+            Element::Node(node) => Some(node),
+            Element::Token(_) => None,
+        })
+    }
+
+    pub fn nodes_mut(&mut self) -> impl Iterator<Item = &mut Node> {
+        self.children.iter_mut().filter_map(|element| match element {
+            Element::Node(node) => Some(node),
+            Element::Token(_) => None,
+        })
+    }
+
+    /// Returns the tokens of the node and all its descendants in the source order.
+    pub fn tokens(&self) -> Vec<&Token> {
+        let mut result = Vec::new();
+        for element in &self.children {
+            match element {
+                Element::Node(node) => result.extend(node.tokens()),
+                Element::Token(token) => result.push(token),
+            }
+        }
+        result
+    }
+
+    pub fn comments(&self) -> impl Iterator<Item = &str> {
+        self.tokens()
+            .into_iter()
+            .filter(|token| token.kind == TokenKind::Comment)
+            .map(|token| token.text.as_str())
+    }
+
+    pub fn documents(&self) -> impl Iterator<Item = &Node> {
+        self.nodes().filter(|node| node.kind == NodeKind::Document)
+    }
+
+    pub fn documents_mut(&mut self) -> impl Iterator<Item = &mut Node> {
+        self.nodes_mut().filter(|node| node.kind == NodeKind::Document)
+    }
+
+    /// Returns the top-level node of a document.
+    pub fn root(&self) -> Option<&Node> {
+        self.nodes().next()
+    }
+
+    pub fn root_mut(&mut self) -> Option<&mut Node> {
+        self.nodes_mut().next()
+    }
+
+    /// Returns the scalar's value (i.e. the text with quotes and escapes processed).
+    pub fn as_str(&self) -> Option<&str> {
+        match &self.kind {
+            NodeKind::Scalar { value, .. } => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the key/value pairs of a mapping.
+    pub fn entries(&self) -> Vec<(&Node, &Node)> {
+        if !matches!(self.kind, NodeKind::Mapping { .. }) {
+            return Vec::new();
+        }
+        let nodes: Vec<&Node> = self.nodes().collect();
+        nodes.chunks_exact(2).map(|pair| (pair[0], pair[1])).collect()
+    }
+
+    /// Returns the value of the mapping entry with the scalar key `key`.
+    pub fn get(&self, key: &str) -> Option<&Node> {
+        self.entries()
+            .into_iter()
+            .find(|(k, _)| k.as_str() == Some(key))
+            .map(|(_, v)| v)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Node> {
+        if !matches!(self.kind, NodeKind::Mapping { .. }) {
+            return None;
+        }
+        let mut nodes = self.nodes_mut();
+        while let (Some(k), Some(v)) = (nodes.next(), nodes.next()) {
+            if k.as_str() == Some(key) {
+                return Some(v);
+            }
+        }
+        None
+    }
+
+    /// Returns the items of a sequence.
+    pub fn items(&self) -> impl Iterator<Item = &Node> {
+        self.nodes()
+            .filter(|_| matches!(self.kind, NodeKind::Sequence { .. }))
+    }
+
+    pub fn items_mut(&mut self) -> impl Iterator<Item = &mut Node> {
+        let sequence = matches!(self.kind, NodeKind::Sequence { .. });
+        self.nodes_mut().filter(move |_| sequence)
+    }
+
+    /// Replaces the source text of a scalar. The text must be a valid YAML scalar, e.g. `"quoted"`, and
+    /// it's written as is.
+    pub fn set_scalar(&mut self, text: &str) {
+        self.children = vec![Element::Token(Token {
+            kind: TokenKind::Text,
+            text: text.to_owned(),
+        })];
+    }
+}
+
+impl Display for Node {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for element in &self.children {
+            match element {
+                Element::Node(node) => node.fmt(f)?,
+                Element::Token(token) => f.write_str(&token.text)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Splits the text between nodes into tokens.
+fn trivia(text: &str, line_start: bool) -> Vec<Element> {
+    let mut result = Vec::new();
+    let mut rest = text;
+    let mut line_start = line_start;
+    while let Some(c) = rest.chars().next() {
+        let next = rest[c.len_utf8()..].chars().next();
+        let separated = |next: Option<char>| next.is_none_or(|c| c.is_whitespace());
+        let (kind, len) = match c {
+            '\n' => (TokenKind::Newline, 1),
+            '\r' if next == Some('\n') => (TokenKind::Newline, 2),
+            ' ' | '\t' | '\r' | '\u{feff}' => (
+                TokenKind::Whitespace,
+                rest.find(|c| !matches!(c, ' ' | '\t' | '\r' | '\u{feff}'))
+                    .unwrap_or(rest.len()),
+            ),
+            '#' => (TokenKind::Comment, rest.find(['\r', '\n']).unwrap_or(rest.len())),
+            '%' if line_start => (
+                TokenKind::Directive,
+                rest.find(['\r', '\n']).unwrap_or(rest.len()),
+            ),
+            '-' | '.' if line_start && (rest.starts_with("---") || rest.starts_with("...")) => {
+                (TokenKind::DocumentMarker, 3)
+            }
+            '-' | '?' | ':' if separated(next) => (TokenKind::Indicator, 1),
+            ',' | '[' | ']' | '{' | '}' => (TokenKind::Indicator, 1),
+            '&' | '!' => (
+                if c == '&' {
+                    TokenKind::Anchor
+                } else {
+                    TokenKind::Tag
+                },
+                rest.find(|c: char| c.is_whitespace() || matches!(c, ',' | '[' | ']' | '{' | '}'))
+                    .unwrap_or(rest.len()),
+            ),
+            _ => (
+                TokenKind::Other,
+                rest.find(char::is_whitespace).unwrap_or(rest.len()),
+            ),
+        };
+        // never produce empty tokens
+        let len = len.max(c.len_utf8());
+        line_start = kind == TokenKind::Newline;
+        result.push(Element::Token(Token {
+            kind,
+            text: rest[..len].to_owned(),
+        }));
+        rest = &rest[len..];
+    }
+    result
+}
+
+struct Builder<'a> {
+    text: &'a str,
+    input: Input<std::str::Chars<'a>>,
+}
+
+impl Builder<'_> {
+    /// Interleaves the nodes with the trivia found between them.
+    fn fill(&self, start: usize, end: usize, nodes: Vec<Node>) -> Vec<Element> {
+        let mut result = Vec::new();
+        let mut cursor = start;
+        for node in nodes {
+            let node_start = node.start.index.max(cursor);
+            result.extend(self.trivia(cursor, node_start));
+            cursor = node.end.index.max(node_start);
+            result.push(Element::Node(node));
+        }
+        result.extend(self.trivia(cursor, end.max(cursor)));
+        result
+    }
+
+    /// Returns the position of the `?` before the first key of a block mapping, if the key is explicit.
+    fn explicit_key_start(&self, start: Position) -> Position {
+        let before = self.text[..start.index].trim_end_matches([' ', '\t']);
+        let spaces = start.index - before.len();
+        match before.strip_suffix('?') {
+            Some(rest) if spaces > 0 && (rest.is_empty() || rest.ends_with([' ', '\t', '\n'])) => Position {
+                index: rest.len(),
+                line: start.line,
+                col: start.col - spaces - 1,
+            },
+            _ => start,
+        }
+    }
+
+    fn trivia(&self, start: usize, end: usize) -> Vec<Element> {
+        let line_start = start == 0 || self.text[..start].ends_with('\n');
+        trivia(&self.text[start..end], line_start)
+    }
+
+    fn node(&mut self, event: Event, marker: Marker) -> Result<Node, ParseError> {
+        let start = self.input.position(&marker);
+        match event {
+            Event::Scalar(value, style, ..) => {
+                let start = self.input.last_start();
+                let end = self.input.last_end();
+                let style = match style {
+                    TScalarStyle::Plain => ScalarStyle::Plain,
+                    TScalarStyle::SingleQuoted => ScalarStyle::SingleQuoted,
+                    TScalarStyle::DoubleQuoted => ScalarStyle::DoubleQuoted,
+                    TScalarStyle::Literal => ScalarStyle::Literal,
+                    TScalarStyle::Folded => ScalarStyle::Folded,
+                };
+                Ok(Node {
+                    kind: NodeKind::Scalar { style, value },
+                    start,
+                    end,
+                    children: vec![Element::Token(Token {
+                        kind: TokenKind::Text,
+                        text: self.text[start.index..end.index].to_owned(),
+                    })],
+                })
+            }
+            Event::Alias(..) => {
+                let end = self.input.last_end();
+                Ok(Node {
+                    kind: NodeKind::Alias,
+                    start,
+                    end,
+                    children: vec![Element::Token(Token {
+                        kind: TokenKind::Text,
+                        text: self.text[start.index..end.index].to_owned(),
+                    })],
+                })
+            }
+            Event::SequenceStart(..) | Event::MappingStart(..) => {
+                let flow = matches!(self.text[start.index..].chars().next(), Some('[' | '{'));
+                let mut nodes = Vec::new();
+                loop {
+                    let (event, marker) = self.input.try_next()?;
+                    if matches!(event, Event::SequenceEnd | Event::MappingEnd) {
+                        break;
+                    }
+                    nodes.push(self.node(event, marker)?);
+                }
+                let kind = if matches!(event, Event::SequenceStart(..)) {
+                    NodeKind::Sequence { flow }
+                } else {
+                    NodeKind::Mapping { flow }
+                };
+                // block mappings are marked at their first key, which for block scalars is the content, not the
+                // header, and doesn't include the `?` of explicit keys
+                let start = match nodes.first() {
+                    Some(first) if first.start.index < start.index => first.start,
+                    _ => start,
+                };
+                let start = match kind {
+                    NodeKind::Mapping { flow: false } => self.explicit_key_start(start),
+                    _ => start,
+                };
+                let end = match nodes.last() {
+                    _ if flow => self.input.last_end(),
+                    Some(last) => last.end,
+                    None => start,
+                };
+                Ok(Node {
+                    kind,
+                    start,
+                    end,
+                    children: self.fill(start.index, end.index, nodes),
+                })
+            }
+            ev => Err(ParseError {
+                location: Some(self.input.location(&marker)),
+                kind: ErrorKind::UnexpectedSyntax,
+                msg: format!("Expected a node, got {ev:?}"),
+            }),
+        }
+    }
+
+    fn document(&mut self, start: Position) -> Result<Node, ParseError> {
+        let mut nodes = Vec::new();
+        loop {
+            let (event, marker) = self.input.try_next()?;
+            if matches!(event, Event::DocumentEnd) {
+                break;
+            }
+            nodes.push(self.node(event, marker)?);
+        }
+        // implicit documents are marked at the first token of the root node, which isn't always its start
+        let start = match nodes.first() {
+            Some(root) if root.start.index < start.index => root.start,
+            _ => start,
+        };
+        let end = nodes.last().map(|node| node.end).unwrap_or(start);
+        Ok(Node {
+            kind: NodeKind::Document,
+            start,
+            end,
+            children: self.fill(start.index, end.index, nodes),
+        })
+    }
+}
+
+/// Parses the YAML stream into a lossless [Node] of the [NodeKind::Stream] kind.
+pub fn parse(text: &str) -> Result<Node, ParseError> {
+    let mut builder = Builder {
+        text,
+        input: Input::try_from(text)?.without_resolving(),
+    };
+    builder.input.next_stream_start()?;
+    let mut documents = Vec::new();
+    loop {
+        let (event, marker) = builder.input.try_next()?;
+        match event {
+            Event::StreamEnd => break,
+            Event::DocumentStart => {
+                let start = builder.input.position(&marker);
+                documents.push(builder.document(start)?);
+            }
+            ev => {
+                return Err(ParseError {
+                    location: Some(builder.input.location(&marker)),
+                    kind: ErrorKind::UnexpectedSyntax,
+                    msg: format!("Expected a document, got {ev:?}"),
+                })
+            }
+        }
+    }
+
+    let last_line = text.rfind('\n').map(|idx| idx + 1).unwrap_or(0);
+    let end = Position {
+        index: text.len(),
+        line: text.matches('\n').count() + 1,
+        col: text[last_line..].chars().count(),
+    };
+    Ok(Node {
+        kind: NodeKind::Stream,
+        start: Position {
+            index: 0,
+            line: 1,
+            col: 0,
+        },
+        end,
+        children: builder.fill(0, text.len(), documents),
+    })
+}
@@ -0,0 +1,221 @@
+use crate::error::ParseError;
+use crate::model::Location;
+use std::fmt::{Display, Formatter};
+
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const GREEN: &str = "\x1b[1;32m";
+const BLUE: &str = "\x1b[1;34m";
+const CYAN: &str = "\x1b[1;36m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Note,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Note => "note",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+
+    fn color(&self) -> &'static str {
+        match self {
+            Severity::Note => GREEN,
+            Severity::Warning => YELLOW,
+            Severity::Error => RED,
+        }
+    }
+}
+
+/// Renders a [ParseError] (or any other message with a location) in a rustc-like format:
+///
+/// ```text
+/// error: Unknown step 'lgo'
+///  --> concord.yml:7:7
+///   |
+/// 7 |     - lgo: "Hello!"
+///   |       ^^^
+///   = in: document->flows->'main' flow
+///   = help: did you mean 'log'?
+/// ```
+pub struct Diagnostic<'a> {
+    source: &'a str,
+    msg: &'a str,
+    location: Option<&'a Location>,
+    severity: Severity,
+    code: Option<&'a str>,
+    file_name: Option<&'a str>,
+    help: Option<String>,
+    color: bool,
+}
+
+impl<'a> Diagnostic<'a> {
+    pub fn new(source: &'a str, error: &'a ParseError) -> Self {
+        Self::from_message(source, &error.msg, error.location.as_ref())
+    }
+
+    /// Creates a diagnostic that isn't a parse error, e.g. a lint.
+    pub fn from_message(source: &'a str, msg: &'a str, location: Option<&'a Location>) -> Self {
+        Self {
+            source,
+            msg,
+            location,
+            severity: Severity::Error,
+            code: None,
+            file_name: None,
+            help: None,
+            color: false,
+        }
+    }
+
+    /// Defaults to the source id of the location (see `Input::with_source_id`) or `<input>`.
+    pub fn with_file_name(mut self, file_name: &'a str) -> Self {
+        self.file_name = Some(file_name);
+        self
+    }
+
+    pub fn with_help<S: ToString>(mut self, help: S) -> Self {
+        self.help = Some(help.to_string());
+        self
+    }
+
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    /// Sets the code shown next to the severity, e.g. `warning[duplicate-flow]`.
+    pub fn with_code(mut self, code: &'a str) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    /// Enables ANSI colors.
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    fn paint(&self, style: &'static str) -> (&'static str, &'static str) {
+        if self.color {
+            (style, RESET)
+        } else {
+            ("", "")
+        }
+    }
+}
+
+/// Returns the number of chars to underline, starting from the beginning of `text`.
+fn token_width(text: &str) -> usize {
+    let mut chars = text.chars();
+    match chars.next() {
+        None => 1,
+        Some(quote @ ('"' | '\'')) => match chars.position(|c| c == quote) {
+            Some(pos) => pos + 2,
+            None => text.chars().count(),
+        },
+        Some(_) => {
+            let mut width = 1;
+            let mut prev = ' ';
+            for c in text.chars().skip(1) {
+                if c.is_whitespace() || matches!(c, ',' | ']' | '}') || (prev == ':' && c == ' ') {
+                    break;
+                }
+                prev = c;
+                width += 1;
+            }
+            // don't underline the key/value separator
+            if prev == ':' && width > 1 {
+                width -= 1;
+            }
+            width
+        }
+    }
+}
+
+impl Display for Diagnostic<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (level, level_end) = self.paint(self.severity.color());
+        let (blue, blue_end) = self.paint(BLUE);
+        let (cyan, cyan_end) = self.paint(CYAN);
+        let (bold, bold_end) = self.paint(BOLD);
+
+        let code = self.code.map(|code| format!("[{code}]")).unwrap_or_default();
+        writeln!(
+            f,
+            "{level}{}{code}{level_end}{bold}: {}{bold_end}",
+            self.severity.as_str(),
+            self.msg
+        )?;
+
+        let file_name = self
+            .file_name
+            .or_else(|| self.location.and_then(Location::source_id))
+            .unwrap_or("<input>");
+        let Some(location) = self.location else {
+            writeln!(f, " {blue}-->{blue_end} {file_name}")?;
+            if let Some(help) = &self.help {
+                writeln!(f, "  {blue}={blue_end} {cyan}help{cyan_end}: {help}")?;
+            }
+            return Ok(());
+        };
+
+        // lines are 1-based, columns are 0-based
+        let start = location.start;
+        let line_no = start.line.max(1);
+        let gutter = " ".repeat(line_no.to_string().len());
+        writeln!(
+            f,
+            "{gutter}{blue}-->{blue_end} {file_name}:{line_no}:{}",
+            start.col + 1
+        )?;
+
+        if let Some(line) = self.source.lines().nth(line_no - 1) {
+            // the columns of the first line don't count the byte order mark
+            let line = if line_no == 1 {
+                line.strip_prefix('\u{feff}').unwrap_or(line)
+            } else {
+                line
+            };
+            let offset = line
+                .char_indices()
+                .nth(start.col)
+                .map(|(idx, _)| idx)
+                .unwrap_or(line.len());
+            let end = location.end;
+            let width = if end.line == start.line && end.col > start.col {
+                end.col - start.col
+            } else {
+                token_width(&line[offset..])
+            };
+            let padding: String = line
+                .chars()
+                .take(start.col)
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+
+            writeln!(f, "{gutter} {blue}|{blue_end}")?;
+            writeln!(f, "{blue}{line_no} |{blue_end} {line}")?;
+            writeln!(
+                f,
+                "{gutter} {blue}|{blue_end} {padding}{level}{}{level_end}",
+                "^".repeat(width)
+            )?;
+        }
+
+        writeln!(f, "{gutter} {blue}={blue_end} in: {:?}", location.path)?;
+        if let Some(help) = &self.help {
+            writeln!(f, "{gutter} {blue}={blue_end} {cyan}help{cyan_end}: {help}")?;
+        }
+
+        Ok(())
+    }
+}
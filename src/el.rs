@@ -0,0 +1,1018 @@
+use crate::error::{ErrorKind, ParseError};
+use crate::model::{DocumentPath, Location, Position};
+
+/// A node of an expression's syntax tree.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "camelCase"))]
+pub struct Expr {
+    pub location: Location,
+    pub kind: ExprKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(
+        tag = "type",
+        content = "value",
+        rename_all = "camelCase",
+        rename_all_fields = "camelCase"
+    )
+)]
+pub enum ExprKind {
+    Null,
+    Boolean(bool),
+    Integer(i64),
+    Float(String),
+    String(String),
+    Identifier(String),
+    /// `target.name`
+    Property {
+        target: Box<Expr>,
+        name: String,
+    },
+    /// `target[index]`
+    Index {
+        target: Box<Expr>,
+        index: Box<Expr>,
+    },
+    /// `target.name(args)`
+    MethodCall {
+        target: Box<Expr>,
+        name: String,
+        args: Vec<Expr>,
+    },
+    /// `function(args)`, e.g. `hasVariable('x')`, or an invocation of a lambda
+    Call {
+        target: Box<Expr>,
+        args: Vec<Expr>,
+    },
+    Unary {
+        op: UnaryOp,
+        operand: Box<Expr>,
+    },
+    Binary {
+        op: BinaryOp,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    /// `condition ? then : otherwise`
+    Ternary {
+        condition: Box<Expr>,
+        then: Box<Expr>,
+        otherwise: Box<Expr>,
+    },
+    /// `x -> body` or `(x, y) -> body`
+    Lambda {
+        params: Vec<String>,
+        body: Box<Expr>,
+    },
+    /// `[a, b]`
+    List(Vec<Expr>),
+    /// `{a, b}`
+    Set(Vec<Expr>),
+    /// `{'a': 1}`
+    Map(Vec<(Expr, Expr)>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "camelCase"))]
+pub enum UnaryOp {
+    /// `-`
+    Negate,
+    /// `!` or `not`
+    Not,
+    /// `empty`
+    Empty,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "camelCase"))]
+pub enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    /// `/` or `div`
+    Divide,
+    /// `%` or `mod`
+    Modulo,
+    /// `+=`, string concatenation
+    Concat,
+    /// `==` or `eq`
+    Equal,
+    /// `!=` or `ne`
+    NotEqual,
+    /// `<` or `lt`
+    Less,
+    /// `<=` or `le`
+    LessOrEqual,
+    /// `>` or `gt`
+    Greater,
+    /// `>=` or `ge`
+    GreaterOrEqual,
+    /// `instanceof`, the right operand is a class name, e.g. `java.lang.String`
+    InstanceOf,
+    /// `&&` or `and`
+    And,
+    /// `||` or `or`
+    Or,
+}
+
+impl Expr {
+    /// Calls `f` for the expression and all its subexpressions, parents first.
+    pub fn walk<'a, F: FnMut(&'a Expr)>(&'a self, f: &mut F) {
+        f(self);
+        match &self.kind {
+            ExprKind::Null
+            | ExprKind::Boolean(_)
+            | ExprKind::Integer(_)
+            | ExprKind::Float(_)
+            | ExprKind::String(_)
+            | ExprKind::Identifier(_) => {}
+            ExprKind::Property { target, .. } => target.walk(f),
+            ExprKind::Index { target, index } => {
+                target.walk(f);
+                index.walk(f);
+            }
+            ExprKind::MethodCall { target, args, .. } | ExprKind::Call { target, args } => {
+                target.walk(f);
+                args.iter().for_each(|arg| arg.walk(f));
+            }
+            ExprKind::Unary { operand, .. } => operand.walk(f),
+            ExprKind::Binary { left, right, .. } => {
+                left.walk(f);
+                right.walk(f);
+            }
+            ExprKind::Ternary {
+                condition,
+                then,
+                otherwise,
+            } => {
+                condition.walk(f);
+                then.walk(f);
+                otherwise.walk(f);
+            }
+            ExprKind::Lambda { body, .. } => body.walk(f),
+            ExprKind::List(items) | ExprKind::Set(items) => items.iter().for_each(|item| item.walk(f)),
+            ExprKind::Map(entries) => entries.iter().for_each(|(key, value)| {
+                key.walk(f);
+                value.walk(f);
+            }),
+        }
+    }
+    /// Returns the identifiers referring to variables: the ones not bound by an enclosing lambda and not
+    /// called as functions (e.g. `hasVariable` in `hasVariable('x')`).
+    pub fn free_variables(&self) -> Vec<&Expr> {
+        fn visit<'a>(expr: &'a Expr, bound: &mut Vec<&'a str>, result: &mut Vec<&'a Expr>) {
+            match &expr.kind {
+                ExprKind::Identifier(name) => {
+                    if !bound.contains(&name.as_str()) {
+                        result.push(expr);
+                    }
+                }
+                ExprKind::Call { target, args } => {
+                    if !matches!(target.kind, ExprKind::Identifier(_)) {
+                        visit(target, bound, result);
+                    }
+                    args.iter().for_each(|arg| visit(arg, bound, result));
+                }
+                ExprKind::Lambda { params, body } => {
+                    let len = bound.len();
+                    bound.extend(params.iter().map(String::as_str));
+                    visit(body, bound, result);
+                    bound.truncate(len);
+                }
+                ExprKind::Property { target, .. } => visit(target, bound, result),
+                ExprKind::Index { target, index } => {
+                    visit(target, bound, result);
+                    visit(index, bound, result);
+                }
+                ExprKind::MethodCall { target, args, .. } => {
+                    visit(target, bound, result);
+                    args.iter().for_each(|arg| visit(arg, bound, result));
+                }
+                ExprKind::Unary { operand, .. } => visit(operand, bound, result),
+                ExprKind::Binary {
+                    op: BinaryOp::InstanceOf,
+                    left,
+                    ..
+                } => visit(left, bound, result),
+                ExprKind::Binary { left, right, .. } => {
+                    visit(left, bound, result);
+                    visit(right, bound, result);
+                }
+                ExprKind::Ternary {
+                    condition,
+                    then,
+                    otherwise,
+                } => {
+                    visit(condition, bound, result);
+                    visit(then, bound, result);
+                    visit(otherwise, bound, result);
+                }
+                ExprKind::List(items) | ExprKind::Set(items) => {
+                    items.iter().for_each(|item| visit(item, bound, result))
+                }
+                ExprKind::Map(entries) => entries.iter().for_each(|(key, value)| {
+                    visit(key, bound, result);
+                    visit(value, bound, result);
+                }),
+                ExprKind::Null
+                | ExprKind::Boolean(_)
+                | ExprKind::Integer(_)
+                | ExprKind::Float(_)
+                | ExprKind::String(_) => {}
+            }
+        }
+
+        let mut result = Vec::new();
+        visit(self, &mut Vec::new(), &mut result);
+        result
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")
+)]
+pub enum TemplatePart {
+    Text {
+        location: Location,
+        text: String,
+    },
+    /// `${...}`, or `#{...}` if `deferred`
+    Expression {
+        location: Location,
+        expr: Expr,
+        deferred: bool,
+    },
+}
+
+/// A string with embedded expressions, e.g. `Hello, ${name}!`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "camelCase"))]
+pub struct Template {
+    pub parts: Vec<TemplatePart>,
+}
+
+impl Template {
+    pub fn expressions(&self) -> impl Iterator<Item = &Expr> {
+        self.parts.iter().filter_map(|part| match part {
+            TemplatePart::Expression { expr, .. } => Some(expr),
+            TemplatePart::Text { .. } => None,
+        })
+    }
+
+    /// Whether the string is a single expression without any text around it, e.g. `${x}`.
+    pub fn is_single_expression(&self) -> bool {
+        matches!(self.parts.as_slice(), [TemplatePart::Expression { .. }])
+    }
+}
+
+/// Maps byte offsets in the expression text to positions in the YAML source, assuming the text starts at
+/// `base` and is written without escapes or line folding (as plain or single-line quoted scalars usually are).
+struct Positions<'a> {
+    base: Position,
+    path: &'a DocumentPath,
+    len: usize,
+    // the offsets of the lines after the first one
+    line_starts: Vec<usize>,
+    // the offsets right after the multi-byte chars and the number of bytes they take beyond one per char,
+    // so lookups don't rescan the text
+    wide_chars: Vec<(usize, usize)>,
+}
+
+impl<'a> Positions<'a> {
+    fn new(text: &str, base: Position, path: &'a DocumentPath) -> Self {
+        let mut line_starts = Vec::new();
+        let mut wide_chars = Vec::new();
+        let mut extra = 0;
+        for (idx, c) in text.char_indices() {
+            if c == '\n' {
+                line_starts.push(idx + 1);
+            }
+            if c.len_utf8() > 1 {
+                extra += c.len_utf8() - 1;
+                wide_chars.push((idx + c.len_utf8(), extra));
+            }
+        }
+        Positions {
+            base,
+            path,
+            len: text.len(),
+            line_starts,
+            wide_chars,
+        }
+    }
+
+    fn extra_bytes(&self, offset: usize) -> usize {
+        match self.wide_chars.partition_point(|&(end, _)| end <= offset) {
+            0 => 0,
+            i => self.wide_chars[i - 1].1,
+        }
+    }
+
+    fn position(&self, offset: usize) -> Position {
+        let offset = offset.min(self.len);
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        let line_start = match line {
+            0 => 0,
+            line => self.line_starts[line - 1],
+        };
+        let col = offset - line_start - (self.extra_bytes(offset) - self.extra_bytes(line_start));
+        Position {
+            index: self.base.index + offset,
+            line: self.base.line + line,
+            col: if line == 0 { self.base.col + col } else { col },
+        }
+    }
+
+    fn location(&self, start: usize, end: usize) -> Location {
+        Location {
+            path: self.path.clone(),
+            start: self.position(start),
+            end: self.position(end),
+        }
+    }
+
+    fn error(&self, start: usize, end: usize, msg: String) -> ParseError {
+        ParseError {
+            location: Some(self.location(start, end)),
+            kind: ErrorKind::UnexpectedSyntax,
+            msg,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Identifier(String),
+    Integer(i64),
+    Float(String),
+    String(String),
+    Punct(&'static str),
+    End,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    start: usize,
+    end: usize,
+}
+
+// longest first
+const PUNCTUATION: [&str; 27] = [
+    "->", "==", "!=", "<=", ">=", "&&", "||", "+=", "+", "-", "*", "/", "%", "<", ">", "!", "?", ":", ".",
+    ",", "(", ")", "[", "]", "{", "}", "=",
+];
+
+fn tokenize(text: &str, positions: &Positions) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let kind = if c.is_alphabetic() || c == '_' || c == '$' {
+            let mut end = start;
+            while let Some((idx, c)) = chars.next_if(|(_, c)| c.is_alphanumeric() || *c == '_' || *c == '$') {
+                end = idx + c.len_utf8();
+            }
+            TokenKind::Identifier(text[start..end].to_owned())
+        } else if c.is_ascii_digit()
+            || (c == '.' && text[start + 1..].starts_with(|c: char| c.is_ascii_digit()))
+        {
+            let (end, float) = number_end(text, start);
+            while chars.next_if(|(idx, _)| *idx < end).is_some() {}
+            let value = &text[start..end];
+            if float {
+                TokenKind::Float(value.to_owned())
+            } else {
+                let number = value.parse().map_err(|e| {
+                    positions.error(start, end, format!("Invalid integer number {value}: {e}"))
+                })?;
+                TokenKind::Integer(number)
+            }
+        } else if c == '\'' || c == '"' {
+            chars.next();
+            let mut value = String::new();
+            let mut closed = false;
+            while let Some((_, s)) = chars.next() {
+                if s == '\\' {
+                    if let Some((_, escaped)) = chars.next() {
+                        value.push(escaped);
+                    }
+                } else if s == c {
+                    closed = true;
+                    break;
+                } else {
+                    value.push(s);
+                }
+            }
+            if !closed {
+                return Err(positions.error(start, text.len(), "Unterminated string literal".to_owned()));
+            }
+            TokenKind::String(value)
+        } else {
+            let rest = &text[start..];
+            let Some(punct) = PUNCTUATION.iter().find(|p| rest.starts_with(**p)) else {
+                let end = start + c.len_utf8();
+                return Err(positions.error(
+                    start,
+                    end,
+                    format!("Unexpected character '{c}' in the expression"),
+                ));
+            };
+            for _ in 0..punct.len() {
+                chars.next();
+            }
+            TokenKind::Punct(punct)
+        };
+        let end = chars.peek().map(|(idx, _)| *idx).unwrap_or(text.len());
+        // don't include the trailing whitespace
+        let end = start + text[start..end].trim_end().len();
+        tokens.push(Token { kind, start, end });
+    }
+    tokens.push(Token {
+        kind: TokenKind::End,
+        start: text.len(),
+        end: text.len(),
+    });
+    Ok(tokens)
+}
+
+/// The binary operators from the lowest to the highest precedence.
+const BINARY_OPS: [&[(&str, BinaryOp)]; 7] = [
+    &[("||", BinaryOp::Or), ("or", BinaryOp::Or)],
+    &[("&&", BinaryOp::And), ("and", BinaryOp::And)],
+    &[
+        ("==", BinaryOp::Equal),
+        ("eq", BinaryOp::Equal),
+        ("!=", BinaryOp::NotEqual),
+        ("ne", BinaryOp::NotEqual),
+    ],
+    &[
+        ("<=", BinaryOp::LessOrEqual),
+        ("le", BinaryOp::LessOrEqual),
+        (">=", BinaryOp::GreaterOrEqual),
+        ("ge", BinaryOp::GreaterOrEqual),
+        ("<", BinaryOp::Less),
+        ("lt", BinaryOp::Less),
+        (">", BinaryOp::Greater),
+        ("gt", BinaryOp::Greater),
+        ("instanceof", BinaryOp::InstanceOf),
+    ],
+    &[("+=", BinaryOp::Concat)],
+    &[("+", BinaryOp::Add), ("-", BinaryOp::Subtract)],
+    &[
+        ("*", BinaryOp::Multiply),
+        ("/", BinaryOp::Divide),
+        ("div", BinaryOp::Divide),
+        ("%", BinaryOp::Modulo),
+        ("mod", BinaryOp::Modulo),
+    ],
+];
+
+/// Returns the end of the number starting at `start` and whether it's a float. Follows JUEL's
+/// `FloatingPointLiteral`: digits, an optional `.` with optional digits (`1.`, `1.5`, `.5`) and at most one
+/// exponent (`1e5`, `1.e-5`).
+fn number_end(text: &str, start: usize) -> (usize, bool) {
+    let bytes = text.as_bytes();
+    let digits = |mut idx: usize| {
+        while bytes.get(idx).is_some_and(u8::is_ascii_digit) {
+            idx += 1;
+        }
+        idx
+    };
+    let mut end = digits(start);
+    let mut float = false;
+    if bytes.get(end) == Some(&b'.') {
+        end = digits(end + 1);
+        float = true;
+    }
+    if matches!(bytes.get(end), Some(b'e' | b'E')) && is_exponent(&text[end + 1..]) {
+        let sign = matches!(bytes.get(end + 1), Some(b'-' | b'+'));
+        end = digits(end + 1 + usize::from(sign));
+        float = true;
+    }
+    (end, float)
+}
+
+/// Whether the text after `e` is the exponent of a number: digits with an optional sign.
+fn is_exponent(text: &str) -> bool {
+    let digits = text.strip_prefix(['-', '+']).unwrap_or(text);
+    digits.starts_with(|c: char| c.is_ascii_digit())
+}
+
+/// The maximum nesting of expressions (parentheses, unary operators, arguments, right operands, etc.).
+/// Parsing and walking the syntax tree is recursive, deeper expressions would overflow the stack. The limit
+/// is low enough for debug builds running in threads with the default 2 MiB stack.
+pub const MAX_DEPTH: usize = 64;
+
+/// The maximum number of binary operators and `.name`, `[index]` and `(args)` segments in an expression.
+/// Chains like `a && b && c` or `a.b.c` are parsed in a loop, but build left-deep trees which are walked
+/// and dropped recursively, so their total length is limited too.
+pub const MAX_CHAIN_LENGTH: usize = 1024;
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    positions: &'a Positions<'a>,
+    depth: usize,
+    // the number of chained operators and segments parsed so far
+    chain_length: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> &TokenKind {
+        &self.tokens[self.pos].kind
+    }
+
+    fn peek_at(&self, offset: usize) -> &TokenKind {
+        let idx = (self.pos + offset).min(self.tokens.len() - 1);
+        &self.tokens[idx].kind
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.pos].clone();
+        if self.pos < self.tokens.len() - 1 {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn is_punct(&self, punct: &str) -> bool {
+        matches!(self.peek(), TokenKind::Punct(p) if *p == punct)
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), TokenKind::Identifier(i) if i == keyword)
+    }
+
+    fn unexpected(&self) -> ParseError {
+        let token = &self.tokens[self.pos];
+        let msg = match &token.kind {
+            TokenKind::End => "Unexpected end of the expression".to_owned(),
+            TokenKind::Identifier(i) => format!("Unexpected '{i}' in the expression"),
+            TokenKind::Integer(i) => format!("Unexpected '{i}' in the expression"),
+            TokenKind::Float(f) => format!("Unexpected '{f}' in the expression"),
+            TokenKind::String(s) => format!("Unexpected string '{s}' in the expression"),
+            TokenKind::Punct(p) => format!("Unexpected '{p}' in the expression"),
+        };
+        self.positions.error(token.start, token.end.max(token.start), msg)
+    }
+
+    /// Enters a nested expression. The callers restore the depth when done.
+    fn nest(&mut self) -> Result<(), ParseError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            let token = &self.tokens[self.pos];
+            let msg = format!("The expression is nested too deeply (more than {MAX_DEPTH} levels)");
+            return Err(self.positions.error(token.start, token.end.max(token.start), msg));
+        }
+        Ok(())
+    }
+
+    /// Adds a link to a chain of binary operators or postfix segments.
+    fn chain(&mut self) -> Result<(), ParseError> {
+        self.chain_length += 1;
+        if self.chain_length > MAX_CHAIN_LENGTH {
+            let token = &self.tokens[self.pos];
+            let msg = format!("The expression is too long (more than {MAX_CHAIN_LENGTH} chained operators)");
+            return Err(self.positions.error(token.start, token.end.max(token.start), msg));
+        }
+        Ok(())
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<Token, ParseError> {
+        if self.is_punct(punct) {
+            Ok(self.next())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn node(&self, start: usize, kind: ExprKind) -> Expr {
+        let end = self.tokens[self.pos.saturating_sub(1)].end;
+        Expr {
+            location: self.positions.location(start, end.max(start)),
+            kind,
+        }
+    }
+
+    fn binary(&self, left: Expr, op: BinaryOp, right: Expr) -> Expr {
+        let start = left.location.start;
+        let mut location = right.location.clone();
+        location.start = start;
+        Expr {
+            location,
+            kind: ExprKind::Binary {
+                op,
+                left: Box::new(left),
+                right: Box::new(right),
+            },
+        }
+    }
+
+    fn start(&self) -> usize {
+        self.tokens[self.pos].start
+    }
+
+    fn expression(&mut self) -> Result<Expr, ParseError> {
+        let depth = self.depth;
+        self.nest()?;
+        let expr = self.lambda_or_ternary()?;
+        self.depth = depth;
+        Ok(expr)
+    }
+
+    fn lambda_or_ternary(&mut self) -> Result<Expr, ParseError> {
+        if let Some(params) = self.lambda_params() {
+            let start = self.start();
+            // skip the parameters and the arrow
+            while !self.is_punct("->") {
+                self.next();
+            }
+            self.next();
+            let body = self.expression()?;
+            return Ok(self.node(
+                start,
+                ExprKind::Lambda {
+                    params,
+                    body: Box::new(body),
+                },
+            ));
+        }
+        self.ternary()
+    }
+
+    /// Returns the parameters if the next tokens are `x ->` or `(x, y) ->`.
+    fn lambda_params(&self) -> Option<Vec<String>> {
+        match (self.peek(), self.peek_at(1)) {
+            (TokenKind::Identifier(name), TokenKind::Punct("->")) => return Some(vec![name.clone()]),
+            (TokenKind::Punct("("), _) => {}
+            _ => return None,
+        }
+        let mut params = Vec::new();
+        let mut offset = 1;
+        loop {
+            match self.peek_at(offset) {
+                TokenKind::Identifier(name) if params.len() * 2 + 1 == offset => params.push(name.clone()),
+                TokenKind::Punct(",") if !params.is_empty() && params.len() * 2 == offset => {}
+                TokenKind::Punct(")") if params.len() * 2 == offset || offset == 1 => break,
+                _ => return None,
+            }
+            offset += 1;
+        }
+        matches!(self.peek_at(offset + 1), TokenKind::Punct("->")).then_some(params)
+    }
+
+    fn ternary(&mut self) -> Result<Expr, ParseError> {
+        let condition = self.binary_chain(0)?;
+        if !self.is_punct("?") {
+            return Ok(condition);
+        }
+        self.next();
+        let then = self.expression()?;
+        self.expect(":")?;
+        let otherwise = self.expression()?;
+        let mut location = otherwise.location.clone();
+        location.start = condition.location.start;
+        Ok(Expr {
+            location,
+            kind: ExprKind::Ternary {
+                condition: Box::new(condition),
+                then: Box::new(then),
+                otherwise: Box::new(otherwise),
+            },
+        })
+    }
+
+    /// Returns the binary operator of the precedence `level` or higher at the current token.
+    fn binary_op(&self, level: usize) -> Option<(usize, BinaryOp)> {
+        BINARY_OPS
+            .iter()
+            .enumerate()
+            .skip(level)
+            .find_map(|(level, ops)| {
+                ops.iter()
+                    .find(|(token, _)| {
+                        if token.starts_with(|c: char| c.is_alphabetic()) {
+                            self.is_keyword(token)
+                        } else {
+                            self.is_punct(token)
+                        }
+                    })
+                    .map(|(_, op)| (level, *op))
+            })
+    }
+
+    /// Parses a left-associative chain of binary operators of the precedence `level` or higher.
+    fn binary_chain(&mut self, level: usize) -> Result<Expr, ParseError> {
+        let mut left = self.unary()?;
+        while let Some((op_level, op)) = self.binary_op(level) {
+            self.chain()?;
+            self.next();
+            let depth = self.depth;
+            self.nest()?;
+            let right = self.binary_chain(op_level + 1)?;
+            self.depth = depth;
+            left = self.binary(left, op, right);
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        let start = self.start();
+        let op = if self.is_punct("-") {
+            UnaryOp::Negate
+        } else if self.is_punct("!") || self.is_keyword("not") {
+            UnaryOp::Not
+        } else if self.is_keyword("empty") {
+            UnaryOp::Empty
+        } else {
+            return self.postfix();
+        };
+        let depth = self.depth;
+        self.nest()?;
+        self.next();
+        let operand = self.unary()?;
+        self.depth = depth;
+        Ok(self.node(
+            start,
+            ExprKind::Unary {
+                op,
+                operand: Box::new(operand),
+            },
+        ))
+    }
+
+    fn arguments(&mut self, close: &str) -> Result<Vec<Expr>, ParseError> {
+        let mut args = Vec::new();
+        if self.is_punct(close) {
+            self.next();
+            return Ok(args);
+        }
+        loop {
+            args.push(self.expression()?);
+            if self.is_punct(",") {
+                self.next();
+            } else {
+                self.expect(close)?;
+                return Ok(args);
+            }
+        }
+    }
+
+    fn postfix(&mut self) -> Result<Expr, ParseError> {
+        let start = self.start();
+        let mut expr = self.primary()?;
+        loop {
+            if self.is_punct(".") || self.is_punct("[") || self.is_punct("(") {
+                self.chain()?;
+            }
+            if self.is_punct(".") {
+                self.next();
+                let TokenKind::Identifier(name) = self.peek().clone() else {
+                    return Err(self.unexpected());
+                };
+                self.next();
+                if self.is_punct("(") {
+                    self.next();
+                    let args = self.arguments(")")?;
+                    expr = self.node(
+                        start,
+                        ExprKind::MethodCall {
+                            target: Box::new(expr),
+                            name,
+                            args,
+                        },
+                    );
+                } else {
+                    expr = self.node(
+                        start,
+                        ExprKind::Property {
+                            target: Box::new(expr),
+                            name,
+                        },
+                    );
+                }
+            } else if self.is_punct("[") {
+                self.next();
+                let index = self.expression()?;
+                self.expect("]")?;
+                expr = self.node(
+                    start,
+                    ExprKind::Index {
+                        target: Box::new(expr),
+                        index: Box::new(index),
+                    },
+                );
+            } else if self.is_punct("(") {
+                self.next();
+                let args = self.arguments(")")?;
+                expr = self.node(
+                    start,
+                    ExprKind::Call {
+                        target: Box::new(expr),
+                        args,
+                    },
+                );
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, ParseError> {
+        let start = self.start();
+        let kind = match self.peek().clone() {
+            TokenKind::Integer(i) => ExprKind::Integer(i),
+            TokenKind::Float(f) => ExprKind::Float(f),
+            TokenKind::String(s) => ExprKind::String(s),
+            TokenKind::Identifier(i) => match i.as_str() {
+                "null" => ExprKind::Null,
+                "true" => ExprKind::Boolean(true),
+                "false" => ExprKind::Boolean(false),
+                // reserved words
+                "and" | "or" | "not" | "eq" | "ne" | "lt" | "gt" | "le" | "ge" | "div" | "mod" | "empty"
+                | "instanceof" => return Err(self.unexpected()),
+                _ => ExprKind::Identifier(i),
+            },
+            TokenKind::Punct("(") => {
+                self.next();
+                let mut expr = self.expression()?;
+                self.expect(")")?;
+                // include the parentheses
+                expr.location = self.node(start, ExprKind::Null).location;
+                return Ok(expr);
+            }
+            TokenKind::Punct("[") => {
+                self.next();
+                let items = self.arguments("]")?;
+                return Ok(self.node(start, ExprKind::List(items)));
+            }
+            TokenKind::Punct("{") => {
+                self.next();
+                return self.set_or_map(start);
+            }
+            _ => return Err(self.unexpected()),
+        };
+        self.next();
+        Ok(self.node(start, kind))
+    }
+
+    /// Parses `{a, b}` or `{k: v}` after the `{`.
+    fn set_or_map(&mut self, start: usize) -> Result<Expr, ParseError> {
+        if self.is_punct("}") {
+            // `{}` is an empty set in EL, but it's almost always meant as an empty map
+            self.next();
+            return Ok(self.node(start, ExprKind::Map(Vec::new())));
+        }
+        let first = self.expression()?;
+        if !self.is_punct(":") {
+            let mut items = vec![first];
+            if self.is_punct(",") {
+                self.next();
+                items.extend(self.arguments("}")?);
+            } else {
+                self.expect("}")?;
+            }
+            return Ok(self.node(start, ExprKind::Set(items)));
+        }
+
+        let mut entries = Vec::new();
+        let mut key = first;
+        loop {
+            self.expect(":")?;
+            let value = self.expression()?;
+            entries.push((key, value));
+            if self.is_punct(",") {
+                self.next();
+                key = self.expression()?;
+            } else {
+                self.expect("}")?;
+                return Ok(self.node(start, ExprKind::Map(entries)));
+            }
+        }
+    }
+}
+
+/// Parses an expression without the `${` and `}` delimiters. `base` is the location of the text in the YAML
+/// source, the locations of the nodes are computed relative to its start.
+pub fn parse_expression(text: &str, base: &Location) -> Result<Expr, ParseError> {
+    let positions = Positions::new(text, base.start, &base.path);
+    let tokens = tokenize(text, &positions)?;
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        positions: &positions,
+        depth: 0,
+        chain_length: 0,
+    };
+    let expr = parser.expression()?;
+    if *parser.peek() != TokenKind::End {
+        return Err(parser.unexpected());
+    }
+    Ok(expr)
+}
+
+/// Returns the offset of the `}` closing the expression that starts at `start`, skipping string literals
+/// and nested braces (e.g. map literals).
+fn expression_end(text: &str, start: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut quote = None;
+    let mut chars = text[start..].char_indices();
+    while let Some((idx, c)) = chars.next() {
+        match (quote, c) {
+            (Some(_), '\\') => {
+                chars.next();
+            }
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '{') => depth += 1,
+            (None, '}') if depth == 0 => return Some(start + idx),
+            (None, '}') => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Parses a string with embedded `${...}` (or `#{...}`) expressions. `\${` is not an expression.
+/// `base` is the location of the text in the YAML source.
+pub fn parse_template(text: &str, base: &Location) -> Result<Template, ParseError> {
+    let positions = Positions::new(text, base.start, &base.path);
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+    let mut literal_start = 0;
+    let mut offset = 0;
+    while offset < text.len() {
+        let rest = &text[offset..];
+        if rest.starts_with("\\${") || rest.starts_with("\\#{") {
+            literal.push_str(&rest[1..3]);
+            offset += 3;
+            continue;
+        }
+        if !(rest.starts_with("${") || rest.starts_with("#{")) {
+            let c = rest.chars().next().unwrap_or_default();
+            literal.push(c);
+            offset += c.len_utf8();
+            continue;
+        }
+
+        if !literal.is_empty() {
+            parts.push(TemplatePart::Text {
+                location: positions.location(literal_start, offset),
+                text: std::mem::take(&mut literal),
+            });
+        }
+        let Some(end) = expression_end(text, offset + 2) else {
+            return Err(positions.error(
+                offset,
+                text.len(),
+                "Unterminated expression, expected '}'".to_owned(),
+            ));
+        };
+        let inner_base = Location {
+            path: base.path.clone(),
+            start: positions.position(offset + 2),
+            end: positions.position(end),
+        };
+        let expr = parse_expression(&text[offset + 2..end], &inner_base)?;
+        parts.push(TemplatePart::Expression {
+            location: positions.location(offset, end + 1),
+            expr,
+            deferred: rest.starts_with('#'),
+        });
+        offset = end + 1;
+        literal_start = offset;
+    }
+    if !literal.is_empty() {
+        parts.push(TemplatePart::Text {
+            location: positions.location(literal_start, text.len()),
+            text: literal,
+        });
+    }
+    Ok(Template { parts })
+}
+
+/// Finds `text` in the part of the YAML `source` covered by `location` and returns its location,
+/// e.g. to get the exact location of the `if` expression from the location of the step. Returns
+/// `location` if the text isn't found (e.g. if the YAML scalar uses escapes or line folding).
+pub fn locate(source: &str, location: &Location, text: &str) -> Location {
+    let span = source
+        .get(location.start.index..location.end.index)
+        .unwrap_or_default();
+    let Some(offset) = span.find(text) else {
+        return location.clone();
+    };
+    let positions = Positions::new(span, location.start, &location.path);
+    positions.location(offset, offset + text.len())
+}
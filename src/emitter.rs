@@ -0,0 +1,883 @@
+use crate::model::{
+    Cardinality, ConcordDocument, Configuration, Exclusive, ExclusiveMode, Flow, FlowStep, Form, FormField,
+    FormFieldSpec, FormFieldType, Import, ImportSecret, Located, Loop, LoopMode, Profile, Resources, Retry,
+    StepDefinition, Trigger, TriggerDefinition, Value, KV,
+};
+
+/// An intermediate YAML tree. Scalars are stored already rendered.
+enum Node {
+    Scalar(String),
+    Literal(String),
+    Sequence(Vec<Node>),
+    // `true` to separate the entries with blank lines
+    Mapping(Vec<(String, Node)>, bool),
+}
+
+fn is_plain_safe(value: &str) -> bool {
+    let Some(first) = value.chars().next() else {
+        return false;
+    };
+    if !(first.is_alphanumeric() || matches!(first, '_' | '$' | '/' | '.')) || value.ends_with(' ') {
+        return false;
+    }
+    if !value.chars().all(|c| {
+        c.is_alphanumeric()
+            || matches!(
+                c,
+                '_' | '-' | '.' | '/' | '$' | '{' | '}' | '(' | ')' | '@' | '+' | ' '
+            )
+    }) {
+        return false;
+    }
+    // must not be read back as something else than a string
+    !is_special_plain(value)
+}
+
+/// Whether the plain scalar is read by YAML loaders as something else than a string: a null, a YAML 1.1
+/// boolean (`yes`, `off`, ...) or number (`0x1F`, `1e5`, `1_000`, `1:30`).
+fn is_special_plain(value: &str) -> bool {
+    let lowercase = value.to_lowercase();
+    if matches!(
+        lowercase.as_str(),
+        "" | "true" | "false" | "yes" | "no" | "on" | "off" | "y" | "n" | "null" | "~"
+    ) || matches!(lowercase.trim_start_matches(['-', '+']), ".inf" | ".nan")
+    {
+        return true;
+    }
+    let number = value.strip_prefix(['-', '+']).unwrap_or(value).replace('_', "");
+    let digits = |text: &str, radix: u32| !text.is_empty() && text.chars().all(|c| c.is_digit(radix));
+    if let Some(prefix) = number.get(..2) {
+        let rest = &number[2..];
+        match prefix {
+            "0x" => return digits(rest, 16),
+            "0o" => return digits(rest, 8),
+            "0b" => return digits(rest, 2),
+            _ => {}
+        }
+    }
+    number.starts_with(|c: char| c.is_ascii_digit() || c == '.') && number.parse::<f64>().is_ok()
+        || number.contains(':') && number.split(':').all(|part| digits(part, 10))
+}
+
+/// Only uses the escapes YAML and JSON have in common, so the result is a valid JSON string as well.
+pub(crate) fn double_quoted(value: &str) -> String {
+    let mut result = String::with_capacity(value.len() + 2);
+    result.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if c.is_control() => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+/// Names, identifiers and keys: plain if possible.
+fn ident(value: &str) -> Node {
+    if is_plain_safe(value) {
+        Node::Scalar(value.to_owned())
+    } else {
+        Node::Scalar(double_quoted(value))
+    }
+}
+
+fn key(value: &str) -> String {
+    match ident(value) {
+        Node::Scalar(s) => s,
+        _ => unreachable!(),
+    }
+}
+
+/// Free-form strings: double-quoted, multiline strings use the literal block style.
+fn string(value: &str) -> Node {
+    let literal_safe = value.contains('\n')
+        && !value.starts_with([' ', '\t', '\n'])
+        && !value.contains(['\r', '\t'])
+        && !value.chars().any(|c| c.is_control() && c != '\n')
+        && value.lines().all(|line| !line.ends_with(' '));
+    if literal_safe {
+        Node::Literal(value.to_owned())
+    } else {
+        Node::Scalar(double_quoted(value))
+    }
+}
+
+fn boolean(value: bool) -> Node {
+    Node::Scalar(value.to_string())
+}
+
+fn strings(values: &[String]) -> Node {
+    Node::Sequence(values.iter().map(|v| string(v)).collect())
+}
+
+fn value(v: &Value) -> Node {
+    match v {
+        Value::String(s) => string(s),
+        Value::Boolean(b) => boolean(*b),
+        Value::Float(f) => Node::Scalar(f.clone()),
+        Value::Integer(i) => Node::Scalar(i.to_string()),
+        Value::Array(items) => Node::Sequence(items.iter().map(value).collect()),
+        Value::Mapping(kvs) => kvs_node(kvs),
+    }
+}
+
+fn kvs_node(kvs: &[KV]) -> Node {
+    Node::Mapping(
+        kvs.iter().map(|kv| (key(&kv.key), value(&kv.value))).collect(),
+        false,
+    )
+}
+
+/// Renders a [Value] used as a mapping key (e.g. a `switch` case label).
+fn value_key(label: &Value) -> String {
+    match label {
+        Value::String(s) => key(s),
+        Value::Boolean(b) => b.to_string(),
+        Value::Float(f) => f.clone(),
+        Value::Integer(i) => i.to_string(),
+        Value::Array(_) | Value::Mapping(_) => {
+            double_quoted(&emit_value(label).trim_end().replace('\n', " "))
+        }
+    }
+}
+
+/// A helper to build mappings with optional entries.
+struct Entries(Vec<(String, Node)>);
+
+impl Entries {
+    fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    fn add(&mut self, name: &str, node: Node) -> &mut Self {
+        self.0.push((name.to_owned(), node));
+        self
+    }
+
+    fn opt<T, F: Fn(&T) -> Node>(&mut self, name: &str, v: &Option<T>, f: F) -> &mut Self {
+        if let Some(v) = v {
+            self.add(name, f(v));
+        }
+        self
+    }
+
+    fn build(&mut self) -> Node {
+        Node::Mapping(std::mem::take(&mut self.0), false)
+    }
+}
+
+fn located<T, F: Fn(&T) -> Node>(f: F) -> impl Fn(&Located<T>) -> Node {
+    move |v| f(&v.value)
+}
+
+fn steps(steps: &[FlowStep]) -> Node {
+    Node::Sequence(steps.iter().map(step).collect())
+}
+
+fn loop_node(l: &Loop) -> Node {
+    Entries::new()
+        .add("items", value(&l.items))
+        .opt("mode", &l.mode, |m| {
+            ident(match m {
+                LoopMode::Serial => "serial",
+                LoopMode::Parallel => "parallel",
+            })
+        })
+        .opt("parallelism", &l.parallelism, value)
+        .build()
+}
+
+fn retry_node(r: &Retry) -> Node {
+    Entries::new()
+        .opt("times", &r.times, value)
+        .opt("delay", &r.delay, value)
+        .opt("in", &r.input, value)
+        .build()
+}
+
+/// Returns the value of the only input parameter `name` if the task call can be written in the short form
+/// (e.g. `- log: "..."`).
+fn short_form_argument<'a>(
+    input: &'a Option<Value>,
+    name: &str,
+    extra: Option<(&str, &str)>,
+) -> Option<&'a Value> {
+    let Some(Value::Mapping(kvs)) = input else {
+        return None;
+    };
+    let expected_len = if extra.is_some() { 2 } else { 1 };
+    if kvs.len() != expected_len || kvs[0].key != name {
+        return None;
+    }
+    if let Some((extra_key, extra_value)) = extra {
+        if !matches!(&kvs[1], KV { key, value: Value::String(v), .. } if key == extra_key && v == extra_value)
+        {
+            return None;
+        }
+    }
+    Some(&kvs[0].value)
+}
+
+fn step_definition(entries: &mut Entries, definition: &StepDefinition) {
+    match definition {
+        StepDefinition::TaskCall {
+            task_name,
+            input,
+            output,
+            error,
+            ignore_errors,
+            looping,
+            meta,
+            retry,
+        } => {
+            let simple = output.is_none()
+                && error.is_none()
+                && ignore_errors.is_none()
+                && looping.is_none()
+                && retry.is_none();
+            let short_form = match task_name.as_str() {
+                _ if !simple => None,
+                "log" => short_form_argument(input, "msg", None)
+                    .map(|v| ("log", v))
+                    .or_else(|| {
+                        short_form_argument(input, "msg", Some(("format", "yaml"))).map(|v| ("logYaml", v))
+                    }),
+                "throw" => short_form_argument(input, "exception", None).map(|v| ("throw", v)),
+                _ => None,
+            };
+            if let Some((name, argument)) = short_form {
+                entries.add(name, value(argument));
+            } else {
+                entries
+                    .add("task", ident(task_name))
+                    .opt("in", input, value)
+                    .opt("out", output, value)
+                    .opt("error", error, |s| steps(s))
+                    .opt("ignoreErrors", ignore_errors, |b| boolean(*b))
+                    .opt("loop", looping, loop_node)
+                    .opt("retry", retry, retry_node);
+            }
+            entries.opt("meta", meta, |m| kvs_node(m));
+        }
+        StepDefinition::Expression {
+            expr,
+            output,
+            error,
+            meta,
+        } => {
+            entries
+                .add("expr", string(expr))
+                .opt("out", output, value)
+                .opt("error", error, |s| steps(s))
+                .opt("meta", meta, |m| kvs_node(m));
+        }
+        StepDefinition::Script {
+            language_or_ref,
+            body,
+            input,
+            output,
+            error,
+            looping,
+            meta,
+            retry,
+        } => {
+            entries
+                .add("script", ident(language_or_ref))
+                .opt("body", body, |b| string(b))
+                .opt("in", input, value)
+                .opt("out", output, value)
+                .opt("error", error, |s| steps(s))
+                .opt("loop", looping, loop_node)
+                .opt("retry", retry, retry_node)
+                .opt("meta", meta, |m| kvs_node(m));
+        }
+        StepDefinition::FlowCall {
+            flow_name,
+            input,
+            output,
+            error,
+            looping,
+            meta,
+            retry,
+        } => {
+            entries
+                .add("call", ident(flow_name))
+                .opt("in", input, value)
+                .opt("out", output, value)
+                .opt("error", error, |s| steps(s))
+                .opt("loop", looping, loop_node)
+                .opt("retry", retry, retry_node)
+                .opt("meta", meta, |m| kvs_node(m));
+        }
+        StepDefinition::Checkpoint { name, meta } => {
+            entries
+                .add("checkpoint", string(name))
+                .opt("meta", meta, |m| kvs_node(m));
+        }
+        StepDefinition::If {
+            expression,
+            then_steps,
+            else_steps,
+            meta,
+        } => {
+            entries
+                .add("if", string(expression))
+                .add("then", steps(then_steps))
+                .opt("else", else_steps, |s| steps(s))
+                .opt("meta", meta, |m| kvs_node(m));
+        }
+        StepDefinition::SetVariables { vars, meta } => {
+            entries
+                .add("set", kvs_node(vars))
+                .opt("meta", meta, |m| kvs_node(m));
+        }
+        StepDefinition::ParallelBlock {
+            steps: block_steps,
+            output,
+            meta,
+        } => {
+            entries
+                .add("parallel", steps(block_steps))
+                .opt("out", output, value)
+                .opt("meta", meta, |m| kvs_node(m));
+        }
+        StepDefinition::Block {
+            steps: block_steps,
+            output,
+            error,
+            looping,
+            meta,
+        } => {
+            let keyword = if error.is_some() { "try" } else { "block" };
+            entries
+                .add(keyword, steps(block_steps))
+                .opt("out", output, value)
+                .opt("error", error, |s| steps(s))
+                .opt("loop", looping, loop_node)
+                .opt("meta", meta, |m| kvs_node(m));
+        }
+        StepDefinition::Switch {
+            expression,
+            cases,
+            default,
+            meta,
+        } => {
+            entries.add("switch", string(expression));
+            for case in cases {
+                entries.0.push((value_key(&case.label), steps(&case.steps)));
+            }
+            entries
+                .opt("default", default, |s| steps(s))
+                .opt("meta", meta, |m| kvs_node(m));
+        }
+        StepDefinition::Suspend { event, meta } => {
+            entries
+                .add("suspend", string(event))
+                .opt("meta", meta, |m| kvs_node(m));
+        }
+        StepDefinition::FormCall {
+            form_name,
+            yield_execution,
+            save_submitted_by,
+            run_as,
+            values,
+            fields,
+            meta,
+        } => {
+            entries
+                .add("form", ident(form_name))
+                .opt("yield", yield_execution, |b| boolean(*b))
+                .opt("saveSubmittedBy", save_submitted_by, |b| boolean(*b))
+                .opt("runAs", run_as, value)
+                .opt("values", values, value)
+                .opt("fields", fields, |f: &Vec<FormField>| form_fields(f))
+                .opt("meta", meta, |m| kvs_node(m));
+        }
+        StepDefinition::Return => {}
+    }
+}
+
+fn step(flow_step: &FlowStep) -> Node {
+    if let StepDefinition::Return = flow_step.step {
+        return Node::Scalar("return".to_owned());
+    }
+    let mut entries = Entries::new();
+    entries.opt("name", &flow_step.step_name, |n| string(n));
+    step_definition(&mut entries, &flow_step.step);
+    entries.build()
+}
+
+fn flows(flows: &[Flow]) -> Node {
+    Node::Mapping(
+        flows
+            .iter()
+            .map(|flow| (key(&flow.name), steps(&flow.steps)))
+            .collect(),
+        true,
+    )
+}
+
+fn form_field_type(spec: &FormFieldSpec) -> Node {
+    let name = match spec.field_type {
+        FormFieldType::String => "string",
+        FormFieldType::Int => "int",
+        FormFieldType::Decimal => "decimal",
+        FormFieldType::Boolean => "boolean",
+        FormFieldType::File => "file",
+        FormFieldType::Date => "date",
+        FormFieldType::DateTime => "dateTime",
+    };
+    let suffix = match spec.cardinality {
+        Cardinality::OneAndOnlyOne => "",
+        Cardinality::OneOrNone => "?",
+        Cardinality::AtLeastOne => "+",
+        Cardinality::Any => "*",
+    };
+    ident(&format!("{name}{suffix}"))
+}
+
+fn form_fields(fields: &[FormField]) -> Node {
+    Node::Sequence(
+        fields
+            .iter()
+            .map(|field| {
+                let spec = &field.spec;
+                let mut entries = Entries::new();
+                entries
+                    .add("type", form_field_type(spec))
+                    .opt("label", &spec.label, |l| string(l))
+                    .opt("value", &spec.value, value)
+                    .opt("allow", &spec.allow, value)
+                    .opt("min", &spec.min, value)
+                    .opt("max", &spec.max, value)
+                    .opt("pattern", &spec.pattern, |p| string(p))
+                    .opt("placeholder", &spec.placeholder, |p| string(p))
+                    .opt("readonly", &spec.readonly, |b| boolean(*b));
+                for kv in &spec.extra {
+                    entries.0.push((key(&kv.key), value(&kv.value)));
+                }
+                Node::Mapping(vec![(key(&field.name), entries.build())], false)
+            })
+            .collect(),
+    )
+}
+
+fn forms(forms: &[Form]) -> Node {
+    Node::Mapping(
+        forms
+            .iter()
+            .map(|form| (key(&form.name), form_fields(&form.fields)))
+            .collect(),
+        true,
+    )
+}
+
+fn exclusive(exclusive: &Exclusive) -> Node {
+    Entries::new()
+        .add("group", string(&exclusive.group))
+        .opt("mode", &exclusive.mode, |m| {
+            ident(match m {
+                ExclusiveMode::Cancel => "cancel",
+                ExclusiveMode::CancelOld => "cancelOld",
+                ExclusiveMode::Wait => "wait",
+            })
+        })
+        .build()
+}
+
+fn configuration(cfg: &Configuration) -> Node {
+    let mut entries = Entries::new();
+    entries
+        .opt("runtime", &cfg.runtime, located(|s: &String| string(s)))
+        .opt("entryPoint", &cfg.entry_point, located(|s: &String| ident(s)))
+        .opt(
+            "dependencies",
+            &cfg.dependencies,
+            located(|v: &Vec<String>| strings(v)),
+        )
+        .opt(
+            "extraDependencies",
+            &cfg.extra_dependencies,
+            located(|v: &Vec<String>| strings(v)),
+        )
+        .opt("arguments", &cfg.arguments, located(|m: &Vec<KV>| kvs_node(m)))
+        .opt("debug", &cfg.debug, located(|b: &bool| boolean(*b)))
+        .opt("meta", &cfg.meta, located(|m: &Vec<KV>| kvs_node(m)))
+        .opt("events", &cfg.events, located(|m: &Vec<KV>| kvs_node(m)))
+        .opt(
+            "requirements",
+            &cfg.requirements,
+            located(|m: &Vec<KV>| kvs_node(m)),
+        )
+        .opt(
+            "processTimeout",
+            &cfg.process_timeout,
+            located(|s: &String| string(s)),
+        )
+        .opt(
+            "suspendTimeout",
+            &cfg.suspend_timeout,
+            located(|s: &String| string(s)),
+        )
+        .opt("exclusive", &cfg.exclusive, exclusive)
+        .opt("out", &cfg.out, located(|v: &Vec<String>| strings(v)))
+        .opt("template", &cfg.template, located(|s: &String| string(s)))
+        .opt(
+            "parallelLoopParallelism",
+            &cfg.parallel_loop_parallelism,
+            located(|i: &i64| Node::Scalar(i.to_string())),
+        );
+    for kv in &cfg.extra {
+        entries.0.push((key(&kv.key), value(&kv.value)));
+    }
+    entries.build()
+}
+
+fn trigger(trigger: &Trigger) -> Node {
+    let mut entries = Entries::new();
+    let event_source = match &trigger.trigger {
+        TriggerDefinition::GitHub {
+            version,
+            use_initiator,
+            use_event_commit_id,
+            ignore_empty_push,
+            entry_point,
+            active_profiles,
+            arguments,
+            conditions,
+            exclusive: ex,
+        } => {
+            entries
+                .opt("version", version, |v| Node::Scalar(v.to_string()))
+                .opt("useInitiator", use_initiator, |b| boolean(*b))
+                .opt("useEventCommitId", use_event_commit_id, |b| boolean(*b))
+                .opt("ignoreEmptyPush", ignore_empty_push, |b| boolean(*b))
+                .add("entryPoint", ident(entry_point))
+                .opt("activeProfiles", active_profiles, |v| strings(v))
+                .opt("arguments", arguments, |m| kvs_node(m))
+                .opt("conditions", conditions, |m| kvs_node(m))
+                .opt("exclusive", ex, exclusive);
+            "github"
+        }
+        TriggerDefinition::Cron {
+            spec,
+            timezone,
+            run_as,
+            entry_point,
+            active_profiles,
+            arguments,
+            exclusive: ex,
+        } => {
+            entries
+                .add("spec", string(spec))
+                .opt("timezone", timezone, |t| string(t))
+                .opt("runAs", run_as, value)
+                .add("entryPoint", ident(entry_point))
+                .opt("activeProfiles", active_profiles, |v| strings(v))
+                .opt("arguments", arguments, |m| kvs_node(m))
+                .opt("exclusive", ex, exclusive);
+            "cron"
+        }
+        TriggerDefinition::Manual {
+            name,
+            entry_point,
+            active_profiles,
+            arguments,
+        } => {
+            entries
+                .opt("name", name, |n| string(n))
+                .add("entryPoint", ident(entry_point))
+                .opt("activeProfiles", active_profiles, |v| strings(v))
+                .opt("arguments", arguments, |m| kvs_node(m));
+            "manual"
+        }
+        TriggerDefinition::OneOps {
+            use_initiator,
+            entry_point,
+            active_profiles,
+            arguments,
+            conditions,
+            exclusive: ex,
+        } => {
+            entries
+                .opt("useInitiator", use_initiator, |b| boolean(*b))
+                .add("entryPoint", ident(entry_point))
+                .opt("activeProfiles", active_profiles, |v| strings(v))
+                .opt("arguments", arguments, |m| kvs_node(m))
+                .opt("conditions", conditions, |m| kvs_node(m))
+                .opt("exclusive", ex, exclusive);
+            "oneops"
+        }
+        TriggerDefinition::Generic {
+            version,
+            entry_point,
+            active_profiles,
+            arguments,
+            conditions,
+            exclusive: ex,
+        } => {
+            entries
+                .opt("version", version, |v| Node::Scalar(v.to_string()))
+                .add("entryPoint", ident(entry_point))
+                .opt("activeProfiles", active_profiles, |v| strings(v))
+                .opt("arguments", arguments, |m| kvs_node(m))
+                .opt("conditions", conditions, |m| kvs_node(m))
+                .opt("exclusive", ex, exclusive);
+            "generic"
+        }
+        TriggerDefinition::Unknown {
+            event_source,
+            entry_point,
+            active_profiles,
+            arguments,
+            conditions,
+            exclusive: ex,
+            options,
+        } => {
+            entries
+                .opt("entryPoint", entry_point, |e| ident(e))
+                .opt("activeProfiles", active_profiles, |v| strings(v))
+                .opt("arguments", arguments, |m| kvs_node(m))
+                .opt("conditions", conditions, |m| kvs_node(m))
+                .opt("exclusive", ex, exclusive);
+            for kv in options {
+                entries.0.push((key(&kv.key), value(&kv.value)));
+            }
+            event_source.as_str()
+        }
+    };
+    Node::Mapping(vec![(key(event_source), entries.build())], false)
+}
+
+fn import_secret(secret: &ImportSecret) -> Node {
+    Entries::new()
+        .opt("org", &secret.org, |o| string(o))
+        .add("name", string(&secret.name))
+        .opt("password", &secret.password, value)
+        .build()
+}
+
+fn import(import: &Import) -> Node {
+    let mut entries = Entries::new();
+    let import_type = match import {
+        Import::Git {
+            url,
+            name,
+            version,
+            path,
+            dest,
+            exclude,
+            secret,
+            ..
+        } => {
+            entries
+                .opt("url", url, |u| string(u))
+                .opt("name", name, |n| string(n))
+                .opt("version", version, |v| string(v))
+                .opt("path", path, |p| string(p))
+                .opt("dest", dest, |d| string(d))
+                .opt("exclude", exclude, |e| strings(e))
+                .opt("secret", secret, import_secret);
+            "git"
+        }
+        Import::Mvn { url, dest, .. } => {
+            entries.add("url", string(url)).opt("dest", dest, |d| string(d));
+            "mvn"
+        }
+        Import::Dir { src, dest, .. } => {
+            entries.add("src", string(src)).opt("dest", dest, |d| string(d));
+            "dir"
+        }
+    };
+    Node::Mapping(vec![(import_type.to_owned(), entries.build())], false)
+}
+
+fn resources(resources: &Resources) -> Node {
+    Entries::new()
+        .opt("concord", &resources.concord, |patterns| {
+            if patterns.excludes.is_empty() {
+                strings(&patterns.includes)
+            } else {
+                Entries::new()
+                    .add("includes", strings(&patterns.includes))
+                    .add("excludes", strings(&patterns.excludes))
+                    .build()
+            }
+        })
+        .build()
+}
+
+fn profile(profile: &Profile) -> Node {
+    Entries::new()
+        .opt("configuration", &profile.configuration, configuration)
+        .opt("flows", &profile.flows, |f| flows(f))
+        .opt("forms", &profile.forms, |f| forms(f))
+        .build()
+}
+
+fn document(document: &ConcordDocument) -> Node {
+    let mut entries = Entries::new();
+    entries
+        .opt("configuration", &document.configuration, configuration)
+        .opt("imports", &document.imports, |i| {
+            Node::Sequence(i.iter().map(import).collect())
+        })
+        .opt("resources", &document.resources, resources)
+        .opt("publicFlows", &document.public_flows, |p| {
+            Node::Sequence(p.iter().map(|f| ident(&f.value)).collect())
+        })
+        .opt("flows", &document.flows, |f| flows(f))
+        .opt("forms", &document.forms, |f| forms(f))
+        .opt("triggers", &document.triggers, |t| {
+            Node::Sequence(t.iter().map(trigger).collect())
+        })
+        .opt("profiles", &document.profiles, |p: &Vec<Profile>| {
+            Node::Mapping(p.iter().map(|p| (key(&p.name), profile(p))).collect(), true)
+        });
+    let Node::Mapping(entries, _) = entries.build() else {
+        unreachable!()
+    };
+    Node::Mapping(entries, true)
+}
+
+struct Printer {
+    out: String,
+}
+
+impl Printer {
+    fn pad(&mut self, indent: usize, inline: bool) {
+        if !inline {
+            self.out.extend(std::iter::repeat_n(' ', indent));
+        }
+    }
+
+    fn literal(&mut self, text: &str, indent: usize) {
+        let chomping = if !text.ends_with('\n') {
+            "-"
+        } else if text.ends_with("\n\n") {
+            "+"
+        } else {
+            ""
+        };
+        self.out.push('|');
+        self.out.push_str(chomping);
+        self.out.push('\n');
+        for line in text.trim_end_matches('\n').split('\n') {
+            if !line.is_empty() {
+                self.pad(indent, false);
+                self.out.push_str(line);
+            }
+            self.out.push('\n');
+        }
+        if chomping == "+" {
+            // the last line break is already printed
+            for _ in 1..text.len() - text.trim_end_matches('\n').len() {
+                self.out.push('\n');
+            }
+        }
+    }
+
+    /// Prints a node after `key:` or `- `. Collections start on the next line, unless `inline` is set.
+    fn value(&mut self, node: &Node, indent: usize) {
+        match node {
+            Node::Scalar(s) => {
+                self.out.push(' ');
+                self.out.push_str(s);
+                self.out.push('\n');
+            }
+            Node::Literal(text) => {
+                self.out.push(' ');
+                self.literal(text, indent + 2);
+            }
+            Node::Sequence(items) if items.is_empty() => self.out.push_str(" []\n"),
+            Node::Mapping(entries, _) if entries.is_empty() => self.out.push_str(" {}\n"),
+            Node::Sequence(items) => {
+                self.out.push('\n');
+                self.sequence(items, indent + 2, false);
+            }
+            Node::Mapping(entries, spaced) => {
+                self.out.push('\n');
+                self.mapping(entries, *spaced, indent + 2, false);
+            }
+        }
+    }
+
+    fn sequence(&mut self, items: &[Node], indent: usize, inline: bool) {
+        for (i, item) in items.iter().enumerate() {
+            self.pad(indent, inline && i == 0);
+            self.out.push('-');
+            match item {
+                Node::Sequence(nested) if !nested.is_empty() => {
+                    self.out.push(' ');
+                    self.sequence(nested, indent + 2, true);
+                }
+                Node::Mapping(entries, spaced) if !entries.is_empty() => {
+                    self.out.push(' ');
+                    self.mapping(entries, *spaced, indent + 2, true);
+                }
+                item => self.value(item, indent),
+            }
+        }
+    }
+
+    fn mapping(&mut self, entries: &[(String, Node)], spaced: bool, indent: usize, inline: bool) {
+        for (i, (key, node)) in entries.iter().enumerate() {
+            if spaced && i > 0 {
+                self.out.push('\n');
+            }
+            self.pad(indent, inline && i == 0);
+            self.out.push_str(key);
+            self.out.push(':');
+            self.value(node, indent);
+        }
+    }
+
+    fn print(node: &Node) -> String {
+        let mut printer = Printer { out: String::new() };
+        match node {
+            Node::Mapping(entries, spaced) if !entries.is_empty() => {
+                printer.mapping(entries, *spaced, 0, false)
+            }
+            Node::Sequence(items) if !items.is_empty() => printer.sequence(items, 0, false),
+            Node::Literal(text) => printer.literal(text, 2),
+            node => {
+                printer.value(node, 0);
+                printer.out.remove(0);
+            }
+        }
+        printer.out
+    }
+}
+
+/// Renders the documents as a YAML stream, separating documents with `---`.
+pub fn emit_stream(documents: &[ConcordDocument]) -> String {
+    documents
+        .iter()
+        .map(emit_document)
+        .collect::<Vec<_>>()
+        .join("---\n")
+}
+
+pub fn emit_document(doc: &ConcordDocument) -> String {
+    Printer::print(&document(doc))
+}
+
+/// Renders the flow as a single-entry mapping: `name: [steps]`.
+pub fn emit_flow(flow: &Flow) -> String {
+    Printer::print(&Node::Mapping(vec![(key(&flow.name), steps(&flow.steps))], false))
+}
+
+/// Renders the step as a sequence item: `- log: "..."`.
+pub fn emit_step(flow_step: &FlowStep) -> String {
+    Printer::print(&Node::Sequence(vec![step(flow_step)]))
+}
+
+pub fn emit_value(v: &Value) -> String {
+    Printer::print(&value(v))
+}
@@ -1,10 +1,14 @@
-use crate::model::{DocumentPath, Location};
+use crate::model::Location;
 use std::fmt::Display;
 
 #[derive(Debug)]
 pub enum ErrorKind {
     ScanError,
     UnexpectedSyntax,
+    /// the input can't be read
+    Io,
+    /// the input isn't valid UTF-8
+    InvalidEncoding,
 }
 
 #[derive(Debug)]
@@ -16,16 +20,9 @@ pub struct ParseError {
 
 impl Display for ParseError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?} @ {:?}: {}", self.kind, self.location, self.msg)
-    }
-}
-
-impl From<yaml_rust2::ScanError> for ParseError {
-    fn from(value: yaml_rust2::ScanError) -> Self {
-        Self {
-            location: Some((DocumentPath::none(), value.marker()).into()),
-            kind: ErrorKind::ScanError,
-            msg: value.to_string(),
+        if let Some(source_id) = self.location.as_ref().and_then(|l| l.source_id()) {
+            write!(f, "{source_id}: ")?;
         }
+        write!(f, "{:?} @ {:?}: {}", self.kind, self.location, self.msg)
     }
 }
@@ -0,0 +1,649 @@
+use crate::cst::{self, Element, Node, NodeKind, ScalarStyle, Token, TokenKind};
+use crate::emitter::double_quoted;
+use crate::error::ParseError;
+
+/// The keys of steps in the order they are written, unknown keys go between the two lists. The cases of a
+/// `switch` keep their order.
+const STEP_KEYS: &[&str] = &[
+    "name",
+    "task",
+    "call",
+    "expr",
+    "script",
+    "log",
+    "logYaml",
+    "throw",
+    "checkpoint",
+    "if",
+    "set",
+    "parallel",
+    "block",
+    "try",
+    "switch",
+    "suspend",
+    "form",
+    "body",
+    "in",
+    "out",
+    "then",
+    "else",
+];
+const STEP_KEYS_LAST: &[&str] = &[
+    "default",
+    "yield",
+    "saveSubmittedBy",
+    "runAs",
+    "values",
+    "fields",
+    "error",
+    "ignoreErrors",
+    "loop",
+    "retry",
+    "meta",
+];
+/// The keys of a `switch` step that aren't its cases.
+const SWITCH_STEP_KEYS: &[&str] = &["switch", "name", "meta"];
+/// Step keys holding nested steps.
+const NESTED_STEPS: &[&str] = &["then", "else", "error", "block", "try", "parallel", "default"];
+
+/// Formats Concord YAML files in the style of [crate::emitter], but on the lossless syntax tree: comments,
+/// anchors and scalars are kept as written.
+///
+/// - block collections are re-indented by two spaces, sequences are indented under their keys;
+/// - the keys of steps are reordered: `name`, the step itself (`task`, `call`, `if`, ...), its parameters
+///   (`in`, `out`, ...), `error`, `loop`, `retry` and `meta` last; the cases of `switch` steps keep their
+///   order;
+/// - plain scalars with expressions (`${...}`) are double-quoted;
+/// - runs of blank lines are collapsed into one, top-level entries, flows, forms and profiles are separated
+///   by a blank line;
+/// - line breaks are written as `\n`.
+///
+/// Collections using rare syntax (complex keys, properties on their own lines, etc.) are kept as they are,
+/// only shifted to the new indentation.
+///
+/// ```
+/// use concord_v2_parser::formatter::format;
+///
+/// let src = "flows:\n  main:\n  - task: greet # say hi\n    out: result\n    in:\n      msg: ${msg}\n";
+/// let formatted = "flows:\n  main:\n    - task: greet # say hi\n      in:\n        msg: \"${msg}\"\n      out: result\n";
+/// assert_eq!(format(src).unwrap(), formatted);
+/// ```
+pub fn format(text: &str) -> Result<String, ParseError> {
+    // the byte order mark would be a part of the first node's text
+    let (bom, text) = match text.strip_prefix('\u{feff}') {
+        Some(rest) => ("\u{feff}", rest),
+        None => ("", text),
+    };
+    let stream = cst::parse(text)?;
+    let mut formatter = Formatter {
+        text,
+        lines: Vec::new(),
+        keep_blanks: false,
+    };
+    if formatter.stream(&stream).is_none() {
+        return Ok(format!("{bom}{text}"));
+    }
+    let mut result = bom.to_owned();
+    for line in &formatter.lines {
+        result.push_str(line);
+        result.push('\n');
+    }
+    Ok(result)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Context {
+    Root,
+    Profiles,
+    Profile,
+    Flows,
+    Forms,
+    Steps,
+    Step,
+    Other,
+}
+
+impl Context {
+    fn spaced(self) -> bool {
+        matches!(
+            self,
+            Context::Root | Context::Profiles | Context::Flows | Context::Forms
+        )
+    }
+
+    fn child(self, key: &str, switch: bool) -> Context {
+        match (self, key) {
+            (Context::Root | Context::Profile, "flows") => Context::Flows,
+            (Context::Root | Context::Profile, "forms") => Context::Forms,
+            (Context::Root, "profiles") => Context::Profiles,
+            (Context::Profiles, _) => Context::Profile,
+            (Context::Flows, _) => Context::Steps,
+            (Context::Steps, _) => Context::Step,
+            (Context::Step, key) if NESTED_STEPS.contains(&key) => Context::Steps,
+            (Context::Step, key) if switch && !SWITCH_STEP_KEYS.contains(&key) => Context::Steps,
+            _ => Context::Other,
+        }
+    }
+}
+
+fn step_key_rank(key: &str) -> usize {
+    if let Some(rank) = STEP_KEYS.iter().position(|k| *k == key) {
+        rank
+    } else if let Some(rank) = STEP_KEYS_LAST.iter().position(|k| *k == key) {
+        STEP_KEYS.len() + 1 + rank
+    } else {
+        STEP_KEYS.len()
+    }
+}
+
+#[derive(Clone, PartialEq)]
+enum Line {
+    Blank,
+    Text(String),
+}
+
+/// Comments, properties and blank lines found between two nodes.
+#[derive(Clone, Default)]
+struct Trivia {
+    /// a comment on the line where the trivia starts, i.e. after the previous node or indicator
+    trailing: Option<String>,
+    /// anchors and tags on the line where the trivia starts
+    props: Vec<String>,
+    /// the following full lines
+    lines: Vec<Line>,
+    /// anything the formatter doesn't handle
+    unusual: bool,
+}
+
+impl Trivia {
+    fn is_empty(&self) -> bool {
+        self.trailing.is_none() && self.props.is_empty() && self.lines.is_empty()
+    }
+}
+
+/// The `-` of a sequence item and the trivia after it.
+#[derive(Clone)]
+struct Dash {
+    col: usize,
+    trivia: Trivia,
+}
+
+struct Entry<'a> {
+    leading: Vec<Line>,
+    key: &'a Node,
+    /// between `:` and the value
+    trivia: Trivia,
+    value: &'a Node,
+    /// the `-` of an indentless sequence value
+    dash: Option<Dash>,
+    trailing: Option<String>,
+}
+
+struct Item<'a> {
+    leading: Vec<Line>,
+    dash: Dash,
+    node: &'a Node,
+    trailing: Option<String>,
+}
+
+/// The tokens between two nodes with their byte offsets.
+type Segment<'a> = Vec<(usize, &'a Token)>;
+type Tokens<'a> = [(usize, &'a Token)];
+
+struct Formatter<'a> {
+    text: &'a str,
+    lines: Vec<String>,
+    // set after a block scalar with the keep chomping (`|+`), whose trailing blank lines are a part of it
+    keep_blanks: bool,
+}
+
+impl<'a> Formatter<'a> {
+    fn col(&self, index: usize) -> usize {
+        let line_start = self.text[..index].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        self.text[line_start..index].chars().count()
+    }
+
+    fn line_start(&self, segment: &Tokens) -> bool {
+        match segment.first() {
+            Some((index, _)) => *index == 0 || self.text[..*index].ends_with('\n'),
+            None => true,
+        }
+    }
+
+    /// Splits the node's children into the nodes and the segments before them, plus the last segment.
+    fn segments<'n>(&self, node: &'n Node) -> (Vec<&'n Node>, Vec<Segment<'n>>, Segment<'n>) {
+        let mut nodes = Vec::new();
+        let mut segments = Vec::new();
+        let mut segment = Vec::new();
+        let mut cursor = node.start.index;
+        for element in &node.children {
+            match element {
+                Element::Token(token) => {
+                    segment.push((cursor, token));
+                    cursor += token.text.len();
+                }
+                Element::Node(child) => {
+                    segments.push(std::mem::take(&mut segment));
+                    nodes.push(child);
+                    cursor = child.end.index.max(child.start.index.max(cursor));
+                }
+            }
+        }
+        (nodes, segments, segment)
+    }
+
+    fn trivia(&self, segment: &Tokens) -> Trivia {
+        let line_start = self.line_start(segment);
+        let mut result = Trivia::default();
+        let mut groups: Vec<Vec<&Token>> = vec![Vec::new()];
+        for (_, token) in segment {
+            if token.kind == TokenKind::Newline {
+                groups.push(Vec::new());
+            } else if let Some(group) = groups.last_mut() {
+                group.push(token);
+            }
+        }
+        let count = groups.len();
+        for (i, group) in groups.into_iter().enumerate() {
+            let significant: Vec<&Token> = group
+                .into_iter()
+                .filter(|token| token.kind != TokenKind::Whitespace)
+                .collect();
+            if i == 0 && !line_start {
+                for token in significant {
+                    match token.kind {
+                        TokenKind::Comment => result.trailing = Some(token.text.clone()),
+                        TokenKind::Anchor | TokenKind::Tag => result.props.push(token.text.clone()),
+                        _ => result.unusual = true,
+                    }
+                }
+            } else if significant.is_empty() {
+                // the last group is the indentation of the next node
+                if i < count - 1 {
+                    result.lines.push(Line::Blank);
+                }
+            } else if i == count - 1 && significant.iter().any(|token| token.kind != TokenKind::Comment) {
+                // properties or indicators before the next node
+                result.unusual = true;
+            } else if significant.iter().all(|token| {
+                matches!(
+                    token.kind,
+                    TokenKind::Comment | TokenKind::DocumentMarker | TokenKind::Directive
+                )
+            }) {
+                let text: Vec<&str> = significant.iter().map(|token| token.text.as_str()).collect();
+                result.lines.push(Line::Text(text.join(" ")));
+            } else {
+                result.unusual = true;
+            }
+        }
+        result
+    }
+
+    /// Splits the segment at the first indicator `indicator`.
+    fn split<'s>(
+        &self,
+        segment: &'s Tokens<'s>,
+        indicator: &str,
+    ) -> Option<(&'s Tokens<'s>, usize, &'s Tokens<'s>)> {
+        let position = segment
+            .iter()
+            .position(|(_, token)| token.kind == TokenKind::Indicator && token.text == indicator)?;
+        Some((
+            &segment[..position],
+            segment[position].0,
+            &segment[position + 1..],
+        ))
+    }
+
+    fn entries(&self, mapping: &'a Node, ctx: Context) -> Option<Vec<Entry<'a>>> {
+        if mapping.kind != (NodeKind::Mapping { flow: false }) {
+            return None;
+        }
+        let (nodes, segments, last) = self.segments(mapping);
+        if nodes.is_empty() || nodes.len() % 2 != 0 || !is_blank(&segments[0]) {
+            return None;
+        }
+        let mut entries: Vec<Entry> = Vec::new();
+        let mut leading = Vec::new();
+        for (i, pair) in nodes.chunks(2).enumerate() {
+            let (key, value) = (pair[0], pair[1]);
+            let key_text = key.to_string();
+            if !matches!(key.kind, NodeKind::Scalar { .. }) || key_text.is_empty() || key_text.contains('\n')
+            {
+                return None;
+            }
+            let (before, _, after) = self.split(&segments[2 * i + 1], ":")?;
+            if !is_blank(before) {
+                return None;
+            }
+            let (trivia, dash) = match self.split(after, "-") {
+                Some((before, index, after)) if matches!(value.kind, NodeKind::Sequence { flow: false }) => {
+                    let dash = Dash {
+                        col: self.col(index),
+                        trivia: self.trivia(after),
+                    };
+                    self.items(value, Some(dash.clone()))?;
+                    (self.trivia(before), Some(dash))
+                }
+                _ => (self.trivia(after), None),
+            };
+            let next = segments.get(2 * i + 2).unwrap_or(&last);
+            let (trivia, next) = empty_value(value, trivia, self.trivia(next));
+            if trivia.unusual || next.unusual || !next.props.is_empty() {
+                return None;
+            }
+            if segments.get(2 * i + 2).is_none() && !next.lines.is_empty() {
+                return None;
+            }
+            entries.push(Entry {
+                leading: std::mem::take(&mut leading),
+                key,
+                trivia,
+                value,
+                dash,
+                trailing: next.trailing,
+            });
+            leading = next.lines;
+        }
+        if ctx == Context::Step {
+            // the keys of a `switch` are its cases, only the keys of the step itself move
+            let switch = entries.iter().any(|entry| entry.key.as_str() == Some("switch"));
+            entries.sort_by_key(|entry| {
+                let key = entry.key.to_string();
+                if switch && !SWITCH_STEP_KEYS.contains(&key.as_str()) {
+                    STEP_KEYS.len()
+                } else {
+                    step_key_rank(&key)
+                }
+            });
+        }
+        Some(entries)
+    }
+
+    fn items(&self, sequence: &'a Node, first: Option<Dash>) -> Option<Vec<Item<'a>>> {
+        if sequence.kind != (NodeKind::Sequence { flow: false }) {
+            return None;
+        }
+        let (nodes, segments, last) = self.segments(sequence);
+        let mut items: Vec<Item> = Vec::new();
+        for (i, node) in nodes.into_iter().enumerate() {
+            let (leading, dash) = match self.split(&segments[i], "-") {
+                Some((before, index, after)) => {
+                    let before = self.trivia(before);
+                    let dash = Dash {
+                        col: self.col(index),
+                        trivia: self.trivia(after),
+                    };
+                    let (dash_trivia, before) = match items.last_mut() {
+                        Some(previous) => empty_value(previous.node, previous.dash.trivia.clone(), before),
+                        None => (Trivia::default(), before),
+                    };
+                    if let Some(previous) = items.last_mut() {
+                        previous.dash.trivia = dash_trivia;
+                    }
+                    if before.unusual || !before.props.is_empty() || dash.trivia.unusual {
+                        return None;
+                    }
+                    match items.last_mut() {
+                        Some(previous) => previous.trailing = before.trailing,
+                        None if before.trailing.is_some() => return None,
+                        None => {}
+                    }
+                    (before.lines, dash)
+                }
+                None if i == 0 && is_blank(&segments[i]) => (Vec::new(), first.clone()?),
+                None => return None,
+            };
+            items.push(Item {
+                leading,
+                dash,
+                node,
+                trailing: None,
+            });
+        }
+        let last = self.trivia(&last);
+        if last.unusual || !last.props.is_empty() || !last.lines.is_empty() {
+            return None;
+        }
+        if let Some(item) = items.last_mut() {
+            item.trailing = last.trailing;
+        }
+        Some(items)
+    }
+
+    fn push(&mut self, indent: usize, text: &str) {
+        self.lines.push(format!("{}{text}", " ".repeat(indent)));
+        self.keep_blanks = false;
+    }
+
+    fn append(&mut self, text: &str) {
+        match self.lines.last_mut() {
+            Some(line) => line.push_str(text),
+            None => self.lines.push(text.to_owned()),
+        }
+    }
+
+    fn comment(&mut self, comment: &Option<String>) {
+        if let Some(comment) = comment {
+            self.append(" ");
+            self.append(comment);
+        }
+    }
+
+    /// Prints the comment and blank lines before a node. Blank lines are collapsed and dropped at the
+    /// start of a collection, `spaced` entries are preceded by exactly one blank line.
+    fn leading(&mut self, lines: &[Line], indent: usize, first: bool, spaced: bool) {
+        if self.keep_blanks {
+            for line in lines {
+                match line {
+                    Line::Blank => self.lines.push(String::new()),
+                    Line::Text(text) => self.push(indent, text),
+                }
+            }
+            self.keep_blanks = false;
+            return;
+        }
+        let mut lines: Vec<&Line> = lines.iter().collect();
+        lines.dedup_by(|a, b| **a == Line::Blank && **b == Line::Blank);
+        if first || spaced {
+            let blanks = lines.iter().take_while(|line| ***line == Line::Blank).count();
+            lines.drain(..blanks);
+        }
+        if spaced {
+            self.lines.push(String::new());
+        }
+        for line in lines {
+            match line {
+                Line::Blank => self.lines.push(String::new()),
+                Line::Text(text) => self.push(indent, text),
+            }
+        }
+    }
+
+    /// Appends the node's text, shifting its continuation lines by `delta` columns.
+    fn verbatim(&mut self, node: &Node, delta: isize) {
+        let text = match &node.kind {
+            NodeKind::Scalar {
+                style: ScalarStyle::Plain,
+                value,
+            } if value.contains("${") => double_quoted(value),
+            _ => node.to_string(),
+        };
+        let mut lines = text.split('\n');
+        if let Some(first) = lines.next() {
+            self.append(first.trim_end_matches('\r'));
+        }
+        for line in lines {
+            let line = line.trim_end_matches('\r');
+            let shifted = if line.is_empty() {
+                String::new()
+            } else if delta >= 0 {
+                format!("{}{line}", " ".repeat(delta as usize))
+            } else {
+                let spaces = line.len() - line.trim_start_matches(' ').len();
+                line[spaces.min(delta.unsigned_abs())..].to_owned()
+            };
+            self.lines.push(shifted);
+        }
+        self.keep_blanks = matches!(
+            node.kind,
+            NodeKind::Scalar {
+                style: ScalarStyle::Literal | ScalarStyle::Folded,
+                ..
+            }
+        ) && text.lines().next().is_some_and(|header| header.contains('+'));
+    }
+
+    fn mapping(&mut self, entries: Vec<Entry<'a>>, indent: usize, ctx: Context, inline: bool) {
+        let switch = entries.iter().any(|entry| entry.key.as_str() == Some("switch"));
+        for (i, entry) in entries.into_iter().enumerate() {
+            self.leading(&entry.leading, indent, i == 0, ctx.spaced() && i > 0);
+            let key = entry.key.to_string();
+            if i == 0 && inline {
+                self.append(&key);
+            } else {
+                self.push(indent, &key);
+            }
+            self.append(":");
+            let delta = indent as isize - entry.key.start.col as isize;
+            let ctx = ctx.child(&key, switch);
+            self.value(entry.value, &entry.trivia, entry.dash, indent + 2, delta, ctx);
+            self.comment(&entry.trailing);
+        }
+    }
+
+    fn sequence(&mut self, items: Vec<Item<'a>>, indent: usize, ctx: Context, inline: bool) {
+        let ctx = ctx.child("", false);
+        for (i, item) in items.into_iter().enumerate() {
+            self.leading(&item.leading, indent, i == 0, false);
+            if i == 0 && inline {
+                self.append("-");
+            } else {
+                self.push(indent, "-");
+            }
+            let delta = indent as isize - item.dash.col as isize;
+            let entries = self.entries(item.node, ctx);
+            let items = self.items(item.node, None);
+            match (entries, items) {
+                (Some(entries), _) if item.dash.trivia.is_empty() && entries[0].leading.is_empty() => {
+                    self.append(" ");
+                    self.mapping(entries, indent + 2, ctx, true);
+                }
+                (_, Some(items)) if item.dash.trivia.is_empty() && items[0].leading.is_empty() => {
+                    self.append(" ");
+                    self.sequence(items, indent + 2, ctx, true);
+                }
+                _ => self.value(item.node, &item.dash.trivia, None, indent + 2, delta, ctx),
+            }
+            self.comment(&item.trailing);
+        }
+    }
+
+    /// Prints the node after `key:` or `-`. Block collections start on the next line at `indent`.
+    fn value(
+        &mut self,
+        node: &'a Node,
+        trivia: &Trivia,
+        dash: Option<Dash>,
+        indent: usize,
+        delta: isize,
+        ctx: Context,
+    ) {
+        if !trivia.props.is_empty() {
+            self.append(" ");
+            self.append(&trivia.props.join(" "));
+        }
+        let block = matches!(
+            node.kind,
+            NodeKind::Mapping { flow: false } | NodeKind::Sequence { flow: false }
+        );
+        if !block && trivia.trailing.is_none() && trivia.lines.is_empty() {
+            if node.end.index > node.start.index {
+                self.append(" ");
+                self.verbatim(node, delta);
+            }
+            return;
+        }
+        self.comment(&trivia.trailing);
+        self.leading(&trivia.lines, indent, true, false);
+        if let Some(entries) = self.entries(node, ctx) {
+            self.mapping(entries, indent, ctx, false);
+        } else if let Some(items) = self.items(node, dash) {
+            self.sequence(items, indent, ctx, false);
+        } else {
+            self.push(indent, "");
+            let delta = if block {
+                indent as isize - node.start.col as isize
+            } else {
+                delta
+            };
+            self.verbatim(node, delta);
+        }
+    }
+
+    fn stream(&mut self, stream: &'a Node) -> Option<()> {
+        let (documents, segments, last) = self.segments(stream);
+        for (document, segment) in documents.into_iter().zip(segments) {
+            self.tail(&segment)?;
+            let (roots, segments, last) = self.segments(document);
+            for (root, segment) in roots.into_iter().zip(segments) {
+                self.tail(&segment)?;
+                if let Some(entries) = self.entries(root, Context::Root) {
+                    self.mapping(entries, 0, Context::Root, false);
+                } else if let Some(items) = self.items(root, None) {
+                    self.sequence(items, 0, Context::Other, false);
+                } else {
+                    self.push(0, "");
+                    self.verbatim(root, -(root.start.col as isize));
+                }
+            }
+            self.tail(&last)?;
+        }
+        self.tail(&last)?;
+        if !self.keep_blanks {
+            while self.lines.last().is_some_and(|line| line.is_empty()) {
+                self.lines.pop();
+            }
+        }
+        Some(())
+    }
+
+    /// Prints the comments, document markers and directives between the documents.
+    fn tail(&mut self, segment: &Segment) -> Option<()> {
+        let trivia = self.trivia(segment);
+        if trivia.unusual || !trivia.props.is_empty() {
+            return None;
+        }
+        self.comment(&trivia.trailing);
+        let first = self.lines.is_empty();
+        self.leading(&trivia.lines, 0, first, false);
+        Some(())
+    }
+}
+
+/// An empty value is located at the start of the next node, so the comments and blank lines found between the
+/// indicator and the empty value are moved to the next node.
+fn empty_value(value: &Node, trivia: Trivia, next: Trivia) -> (Trivia, Trivia) {
+    if value.end.index > value.start.index || trivia.unusual || next.unusual {
+        return (trivia, next);
+    }
+    let mut lines = trivia.lines;
+    lines.extend(next.lines);
+    let next = Trivia {
+        trailing: trivia.trailing.or(next.trailing),
+        lines,
+        ..next
+    };
+    let trivia = Trivia {
+        props: trivia.props,
+        ..Trivia::default()
+    };
+    (trivia, next)
+}
+
+fn is_blank(segment: &Tokens) -> bool {
+    segment
+        .iter()
+        .all(|(_, token)| matches!(token.kind, TokenKind::Whitespace | TokenKind::Newline))
+}
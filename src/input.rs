@@ -1,28 +1,35 @@
 use crate::error::{ErrorKind, ParseError};
-use crate::model::{DocumentPath, Location, Value, KV};
+use crate::model::{DocumentPath, Location, Position, Value, KV};
+use crate::resolve::{ResolveError, Resolver};
+use crate::source::Source;
+use std::io::Read;
+use std::path::Path;
 use std::str::Chars;
+use std::sync::Arc;
+use yaml_rust2::scanner::TScalarStyle;
 
 pub type Event = yaml_rust2::Event;
 pub type Marker = yaml_rust2::scanner::Marker;
 
+/// An empty span at the marker. yaml-rust2 markers count chars, so the index is a byte offset only for ASCII
+/// text, [Input::location] computes the byte offset from the source text.
 impl From<(DocumentPath, Marker)> for Location {
     fn from((path, marker): (DocumentPath, Marker)) -> Self {
-        Location {
-            path,
-            index: marker.index(),
-            line: marker.line(),
-            col: marker.col(),
-        }
+        (path, &marker).into()
     }
 }
 
 impl From<(DocumentPath, &Marker)> for Location {
     fn from((path, marker): (DocumentPath, &Marker)) -> Self {
-        Location {
-            path,
+        let position = Position {
             index: marker.index(),
             line: marker.line(),
             col: marker.col(),
+        };
+        Location {
+            path,
+            start: position,
+            end: position,
         }
     }
 }
@@ -42,22 +49,236 @@ fn parse_f64(value: &str) -> Result<f64, ParseError> {
     }
 }
 
+fn scan_error(source: &Source, document_path: DocumentPath, e: yaml_rust2::ScanError) -> ParseError {
+    let marker = e.marker();
+    let position = Position {
+        index: source.byte_index(marker.index()),
+        line: marker.line(),
+        col: marker.col(),
+    };
+    ParseError {
+        location: Some(Location {
+            path: document_path,
+            start: position,
+            end: position,
+        }),
+        kind: ErrorKind::ScanError,
+        msg: e.to_string(),
+    }
+}
+
+fn resolve_error(source: &Source, document_path: DocumentPath, e: ResolveError) -> ParseError {
+    match e {
+        ResolveError::Eof => ParseError {
+            location: None,
+            kind: ErrorKind::ScanError,
+            msg: "EOF".to_owned(),
+        },
+        ResolveError::Scan(e) => scan_error(source, document_path, e),
+        // invalid aliases and merge keys leave the event stream out of sync, so they are not recoverable either
+        ResolveError::Syntax(marker, msg) => {
+            let position = Position {
+                index: source.byte_index(marker.index()),
+                line: marker.line(),
+                col: marker.col(),
+            };
+            ParseError {
+                location: Some(Location {
+                    path: document_path,
+                    start: position,
+                    end: position,
+                }),
+                kind: ErrorKind::ScanError,
+                msg,
+            }
+        }
+    }
+}
+
 pub struct Input<T: Iterator<Item = char>> {
+    source_id: Option<Arc<str>>,
     document_path: Vec<String>,
+    source: Source,
     yaml: yaml_rust2::parser::Parser<T>,
+    resolver: Resolver,
     eof: bool,
+    // number of complete nodes consumed at each nesting level, used to resynchronize after an error
+    node_counts: Vec<usize>,
+    // whether the sequences and mappings being consumed use the flow style
+    flow_styles: Vec<bool>,
+    // the span of the last consumed scalar or alias, `last_end` is also updated by flow collections
+    last_start: Position,
+    last_end: Position,
+    recovery: bool,
+    errors: Vec<ParseError>,
+}
+
+impl<T: Iterator<Item = char>> Input<T> {
+    /// `chars` are the chars of `text` after the byte order mark `bom`, the positions of nodes are counted
+    /// from the start of the text, including it.
+    fn new(chars: T, text: Arc<str>, bom: &str) -> Self {
+        let source = Source::new(text, bom);
+        let yaml = yaml_rust2::parser::Parser::new(chars);
+        Input {
+            source_id: None,
+            document_path: Vec::new(),
+            source,
+            yaml,
+            resolver: Resolver::default(),
+            eof: false,
+            node_counts: vec![0],
+            flow_styles: Vec::new(),
+            last_start: Position::default(),
+            last_end: Position::default(),
+            recovery: false,
+            errors: Vec::new(),
+        }
+    }
 }
 
+/// The byte order mark, which YAML allows at the start of the stream.
+const BOM: &str = "\u{feff}";
+
+/// Splits the byte order mark off the text.
+fn split_bom(text: &str) -> (&'static str, &str) {
+    match text.strip_prefix(BOM) {
+        Some(rest) => (BOM, rest),
+        None => ("", text),
+    }
+}
+
+/// The text may start with the byte order mark. The positions of nodes are byte offsets in the whole text,
+/// including the byte order mark.
 impl<'a> TryFrom<&'a str> for Input<Chars<'a>> {
     type Error = ParseError;
 
     fn try_from(value: &'a str) -> Result<Self, Self::Error> {
-        let yaml = yaml_rust2::parser::Parser::new(value.chars());
-        Ok(Input {
-            document_path: Vec::new(),
-            yaml,
-            eof: false,
-        })
+        let (bom, text) = split_bom(value);
+        Ok(Input::new(text.chars(), value.into(), bom))
+    }
+}
+
+fn encoding_error(bytes: &[u8], index: usize, msg: String) -> ParseError {
+    // the position of the invalid byte, counted in the valid text before it
+    let valid = String::from_utf8_lossy(&bytes[..index]);
+    let valid = split_bom(&valid).1;
+    let position = Position {
+        index,
+        line: valid.matches('\n').count() + 1,
+        col: valid.rsplit('\n').next().unwrap_or_default().chars().count(),
+    };
+    ParseError {
+        location: Some(Location {
+            path: DocumentPath::default(),
+            start: position,
+            end: position,
+        }),
+        kind: ErrorKind::InvalidEncoding,
+        msg,
+    }
+}
+
+fn check_utf16(bytes: &[u8]) -> Result<(), ParseError> {
+    if bytes.starts_with(&[0xFF, 0xFE]) || bytes.starts_with(&[0xFE, 0xFF]) {
+        let msg = "UTF-16 encoded input is not supported, expected UTF-8".to_owned();
+        return Err(encoding_error(bytes, 0, msg));
+    }
+    Ok(())
+}
+
+fn invalid_utf8(bytes: &[u8], e: std::str::Utf8Error) -> ParseError {
+    let index = e.valid_up_to();
+    encoding_error(bytes, index, format!("Invalid UTF-8 at byte {index}"))
+}
+
+/// Decodes UTF-8 (with or without the byte order mark). As for `&str`, the positions of nodes are byte offsets
+/// in the whole input, including the byte order mark.
+impl<'a> TryFrom<&'a [u8]> for Input<Chars<'a>> {
+    type Error = ParseError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        check_utf16(value)?;
+        let text = std::str::from_utf8(value).map_err(|e| invalid_utf8(value, e))?;
+        Input::try_from(text)
+    }
+}
+
+/// The chars of an owned string, for the inputs that are read from files and streams. The text is shared
+/// with the source used to compute the positions of nodes.
+pub struct OwnedChars {
+    text: Arc<str>,
+    index: usize,
+}
+
+impl Iterator for OwnedChars {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.text[self.index..].chars().next()?;
+        self.index += c.len_utf8();
+        Some(c)
+    }
+}
+
+impl TryFrom<String> for Input<OwnedChars> {
+    type Error = ParseError;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        let text: Arc<str> = text.into();
+        let bom = split_bom(&text).0;
+        // the byte order mark is skipped rather than removed, which would move the whole text
+        Ok(Input::new(
+            OwnedChars {
+                index: bom.len(),
+                text: text.clone(),
+            },
+            text.clone(),
+            bom,
+        ))
+    }
+}
+
+impl TryFrom<Vec<u8>> for Input<OwnedChars> {
+    type Error = ParseError;
+
+    fn try_from(bytes: Vec<u8>) -> Result<Self, Self::Error> {
+        check_utf16(&bytes)?;
+        let text = String::from_utf8(bytes).map_err(|e| invalid_utf8(e.as_bytes(), e.utf8_error()))?;
+        Input::try_from(text)
+    }
+}
+
+impl Input<OwnedChars> {
+    /// Reads the whole stream and decodes it as UTF-8 (with or without the byte order mark).
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self, ParseError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).map_err(|e| ParseError {
+            location: None,
+            kind: ErrorKind::Io,
+            msg: format!("Can't read the input: {e}"),
+        })?;
+        Input::try_from(bytes)
+    }
+
+    /// Reads the file and decodes it as UTF-8 (with or without the byte order mark). The path is used as the
+    /// source id, see [Input::with_source_id].
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, ParseError> {
+        let path = path.as_ref();
+        let source_id: Arc<str> = path.to_string_lossy().into();
+        let with_source_id = |mut e: ParseError| {
+            if let Some(location) = &mut e.location {
+                location.path = location.path.clone().with_source_id(Some(source_id.clone()));
+            }
+            e
+        };
+        let bytes = std::fs::read(path).map_err(|e| ParseError {
+            location: None,
+            kind: ErrorKind::Io,
+            msg: format!("Can't read {}: {e}", path.display()),
+        })?;
+        Ok(Input::try_from(bytes)
+            .map_err(with_source_id)?
+            .with_source_id(source_id.clone()))
     }
 }
 
@@ -67,7 +288,7 @@ macro_rules! match_next {
         match $input.try_next()? {
             (ev @ $pat, marker) => Ok((ev, marker)),
             (ev, marker) => Err(ParseError {
-                location: Some(($input.current_document_path(), marker).into()),
+                location: Some($input.location(&marker)),
                 kind: ErrorKind::UnexpectedSyntax,
                 msg: format!("Expected {}, got {ev:?}", stringify!($pat)),
             }),
@@ -88,6 +309,20 @@ macro_rules! parse_until {
         }
         items
     }};
+    // in recovery mode, skips the items that failed to parse. Each item consists of `$nodes` YAML nodes
+    // (1 for sequence items, 2 for mapping entries)
+    ($input:ident, $pat:pat, $parser:ident, $nodes:expr) => {{
+        let mut items = Vec::new();
+        loop {
+            if let Some(item) = $input.recoverable($nodes, $parser)? {
+                items.push(item);
+            }
+            if matches!($input.peek()?, ($pat, _)) {
+                break;
+            }
+        }
+        items
+    }};
 }
 
 impl<T: Iterator<Item = char>> Input<T> {
@@ -109,8 +344,75 @@ impl<T: Iterator<Item = char>> Input<T> {
         Ok(result)
     }
 
+    /// Sets the identifier of the source, usually the file name. It's included in all locations, so the
+    /// errors and the nodes of documents from different files can be told apart.
+    pub fn with_source_id<S: Into<Arc<str>>>(mut self, source_id: S) -> Self {
+        self.source_id = Some(source_id.into());
+        self
+    }
+
+    pub fn source_id(&self) -> Option<&str> {
+        self.source_id.as_deref()
+    }
+
+    /// The whole input text, including the byte order mark. The indexes of the positions are byte offsets
+    /// in it.
+    pub fn text(&self) -> &Arc<str> {
+        self.source.text()
+    }
+
+    /// Passes aliases and merge keys through as written instead of resolving them.
+    pub(crate) fn without_resolving(mut self) -> Self {
+        self.resolver = Resolver::disabled();
+        self
+    }
+
     pub fn current_document_path(&self) -> DocumentPath {
-        DocumentPath::new(&self.document_path)
+        DocumentPath::new(&self.document_path).with_source_id(self.source_id.clone())
+    }
+
+    /// Returns the position of the marker in the source text.
+    pub fn position(&self, marker: &Marker) -> Position {
+        Position {
+            index: self.source.byte_index(marker.index()),
+            line: marker.line(),
+            col: marker.col(),
+        }
+    }
+
+    /// Returns the location of the marker (an empty span).
+    pub fn location(&self, marker: &Marker) -> Location {
+        let position = self.position(marker);
+        Location {
+            path: self.current_document_path(),
+            start: position,
+            end: position,
+        }
+    }
+
+    /// Returns the span between the marker and the end of the last consumed node.
+    pub fn location_since(&self, marker: &Marker) -> Location {
+        let start = self.position(marker);
+        Location {
+            path: self.current_document_path(),
+            start,
+            end: if self.last_end.index >= start.index {
+                self.last_end
+            } else {
+                start
+            },
+        }
+    }
+
+    /// Returns the start of the last consumed scalar or alias. Unlike the event's marker, includes
+    /// the header of block scalars.
+    pub fn last_start(&self) -> Position {
+        self.last_start
+    }
+
+    /// Returns the end of the last consumed scalar, alias or flow collection.
+    pub fn last_end(&self) -> Position {
+        self.last_end
     }
 
     pub fn try_next(&mut self) -> Result<(Event, Marker), ParseError> {
@@ -121,11 +423,165 @@ impl<T: Iterator<Item = char>> Input<T> {
                 msg: "EOF".to_owned(),
             });
         }
-        let (event, marker) = &self.yaml.next_token()?;
-        if matches!(event, Event::StreamEnd) {
-            self.eof = true;
+        let item = match self.resolver.next(&mut self.yaml) {
+            Ok(item) => item,
+            Err(e) => return Err(resolve_error(&self.source, self.current_document_path(), e)),
+        };
+        let (event, mut marker) = item.event;
+        let start = self.position(&marker);
+        match &event {
+            Event::StreamEnd => self.eof = true,
+            _ if item.copied => self.copied_event(&event, start),
+            Event::SequenceStart(..) | Event::MappingStart(..) => {
+                let flow = matches!(self.source.char_at(start.index), Some('[' | '{'));
+                if !flow && matches!(event, Event::MappingStart(..)) {
+                    // yaml-rust2 marks block mappings at the first ':', use the first key instead
+                    if let Ok(key) = self.resolver.peek(&mut self.yaml) {
+                        marker = key.event.1;
+                    }
+                }
+                self.flow_styles.push(flow);
+                self.node_counts.push(0);
+            }
+            Event::SequenceEnd | Event::MappingEnd => {
+                if let Some(true) = self.flow_styles.pop() {
+                    // flow collections end with the closing bracket, block collections end with their last item
+                    self.last_end = self.source.advance(start, start.index + 1);
+                }
+                self.node_counts.pop();
+                self.count_node();
+            }
+            Event::Scalar(value, style, ..) => {
+                let start = match style {
+                    TScalarStyle::Literal | TScalarStyle::Folded => self.source.block_scalar_start(start),
+                    _ => start,
+                };
+                let end = self.source.scalar_end(start.index, *style, value);
+                self.last_start = start;
+                self.last_end = self.source.advance(start, end);
+                self.count_node();
+            }
+            Event::Alias(..) => {
+                self.last_start = start;
+                self.last_end = self.source.advance(start, self.source.alias_end(start.index));
+                self.count_node();
+            }
+            _ => {}
+        }
+        Ok((event, marker))
+    }
+
+    /// Tracks an event copied from an anchored node or a merged mapping. Its marker points to the alias
+    /// or the merge key, so the whole copy spans the alias (or is empty for a merge key).
+    fn copied_event(&mut self, event: &Event, start: Position) {
+        let source = &self.source;
+        let end = match source.char_at(start.index) {
+            Some('*') => source.advance(start, source.alias_end(start.index)),
+            _ => start,
+        };
+        match event {
+            Event::SequenceStart(..) | Event::MappingStart(..) => {
+                self.flow_styles.push(false);
+                self.node_counts.push(0);
+            }
+            Event::SequenceEnd | Event::MappingEnd => {
+                self.flow_styles.pop();
+                self.last_end = end;
+                self.node_counts.pop();
+                self.count_node();
+            }
+            Event::Scalar(..) => {
+                self.last_start = start;
+                self.last_end = end;
+                self.count_node();
+            }
+            _ => {}
+        }
+    }
+
+    fn count_node(&mut self) {
+        if let Some(count) = self.node_counts.last_mut() {
+            *count += 1;
+        }
+    }
+
+    /// Enables or disables the error recovery mode. In recovery mode, [Input::recoverable] collects
+    /// errors instead of returning them.
+    pub fn set_recovery(&mut self, recovery: bool) {
+        self.recovery = recovery;
+    }
+
+    pub fn take_errors(&mut self) -> Vec<ParseError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    /// Runs the parser `f` that consumes `nodes` YAML nodes at the current nesting level.
+    /// In recovery mode, if `f` fails, the error is recorded and the input is advanced past the
+    /// offending nodes, so the parsing can continue with the next sequence item or mapping key.
+    /// Scan errors (i.e. invalid YAML) are not recoverable.
+    pub fn recoverable<O, F>(&mut self, nodes: usize, f: F) -> Result<Option<O>, ParseError>
+    where
+        F: FnOnce(&mut Self) -> Result<O, ParseError>,
+    {
+        if !self.recovery {
+            return f(self).map(Some);
+        }
+
+        let depth = self.node_counts.len();
+        let start = self.node_counts[depth - 1];
+        let context = self.document_path.len();
+
+        match f(self) {
+            Ok(result) => Ok(Some(result)),
+            Err(
+                e @ ParseError {
+                    kind: ErrorKind::ScanError,
+                    ..
+                },
+            ) => Err(e),
+            Err(e) => {
+                self.document_path.truncate(context);
+
+                // leave any nested blocks the parser didn't finish
+                while self.node_counts.len() > depth {
+                    self.try_next()?;
+                }
+                if self.node_counts.len() < depth {
+                    // the parser went past the end of the enclosing block, nothing we can do here
+                    return Err(e);
+                }
+
+                // skip the rest of the offending item
+                loop {
+                    let consumed = self.node_counts[depth - 1] - start;
+                    if consumed > 0 && consumed % nodes == 0 {
+                        break;
+                    }
+                    if matches!(
+                        self.peek()?,
+                        (
+                            Event::SequenceEnd | Event::MappingEnd | Event::DocumentEnd | Event::StreamEnd,
+                            _
+                        )
+                    ) {
+                        break;
+                    }
+                    self.skip_node()?;
+                }
+
+                self.errors.push(e);
+                Ok(None)
+            }
         }
-        Ok((event.clone(), *marker))
+    }
+
+    fn skip_node(&mut self) -> Result<(), ParseError> {
+        let depth = self.node_counts.len();
+        self.try_next()?;
+        while self.node_counts.len() > depth {
+            self.try_next()?;
+        }
+        Ok(())
     }
 
     pub fn next_stream_start(&mut self) -> Result<(Event, Marker), ParseError> {
@@ -164,7 +620,7 @@ impl<T: Iterator<Item = char>> Input<T> {
         match self.try_next()? {
             (Event::Scalar(value, ..), marker) => Ok((value, marker)),
             (ev, marker) => Err(ParseError {
-                location: Some((self.current_document_path(), marker).into()),
+                location: Some(self.location(&marker)),
                 kind: ErrorKind::UnexpectedSyntax,
                 msg: format!("Expected a string value, got {ev:?}"),
             }),
@@ -177,7 +633,7 @@ impl<T: Iterator<Item = char>> Input<T> {
         let (value, _) = self.next_value()?;
         self.leave_context();
         Ok(KV {
-            location: (self.current_document_path(), marker).into(),
+            location: self.location_since(&marker),
             key,
             value,
         })
@@ -201,7 +657,6 @@ impl<T: Iterator<Item = char>> Input<T> {
                         } else if let Ok(value) = scalar.parse::<i64>() {
                             Ok(Value::Integer(value))
                         } else if let Ok(value) = scalar.parse::<bool>() {
-                            // TODO handle "yes/no", etc
                             Ok(Value::Boolean(value))
                         } else {
                             Ok(Value::String(scalar))
@@ -226,7 +681,7 @@ impl<T: Iterator<Item = char>> Input<T> {
                 Ok(Value::Mapping(result))
             }
             ev => Err(ParseError {
-                location: Some((self.current_document_path(), marker).into()),
+                location: Some(self.location(&marker)),
                 kind: ErrorKind::UnexpectedSyntax,
                 msg: format!("Expected a value, got {ev:?}"),
             }),
@@ -234,15 +689,20 @@ impl<T: Iterator<Item = char>> Input<T> {
     }
 
     pub fn peek(&mut self) -> Result<&(Event, Marker), ParseError> {
-        let result = self.yaml.peek()?;
-        Ok(result)
+        match self.resolver.peek(&mut self.yaml) {
+            Ok(item) => Ok(&item.event),
+            Err(e) => {
+                let path = DocumentPath::new(&self.document_path).with_source_id(self.source_id.clone());
+                Err(resolve_error(&self.source, path, e))
+            }
+        }
     }
 
     pub fn peek_string(&mut self) -> Result<Option<(String, Marker)>, ParseError> {
         match self.peek().cloned()? {
             (Event::Scalar(value, ..), marker) => Ok(Some((value, marker))),
             (ev, marker) => Err(ParseError {
-                location: Some((self.current_document_path(), marker).into()),
+                location: Some(self.location(&marker)),
                 kind: ErrorKind::UnexpectedSyntax,
                 msg: format!("Expected to peek a scalar, got {ev:?}"),
             }),
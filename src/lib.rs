@@ -1,4 +1,16 @@
+pub mod analysis;
+pub mod call_graph;
+pub mod cst;
+pub mod diagnostic;
+pub mod el;
+pub mod emitter;
 pub mod error;
+pub mod formatter;
 pub mod input;
+pub mod lint;
 pub mod model;
 pub mod parser;
+pub mod project;
+mod resolve;
+mod source;
+pub mod visit;
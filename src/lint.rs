@@ -0,0 +1,379 @@
+use crate::analysis::{all_flows, undefined_flows, undefined_variables, unreachable_flows};
+use crate::diagnostic::Severity;
+use crate::emitter::double_quoted;
+use crate::error::ParseError;
+use crate::model::{ConcordDocument, Flow, Location};
+use std::collections::HashMap;
+
+/// A parsed document together with the name and the text of the file it comes from.
+pub struct LintDocument<'a> {
+    pub file: &'a str,
+    pub source: &'a str,
+    pub document: &'a ConcordDocument,
+}
+
+#[derive(Debug)]
+pub struct Lint {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub file: String,
+    pub location: Option<Location>,
+    pub msg: String,
+    pub help: Option<String>,
+}
+
+impl Lint {
+    /// Reports a parse error as a lint of the `parse-error` rule.
+    pub fn from_parse_error(file: &str, error: ParseError) -> Self {
+        Lint {
+            rule: PARSE_ERROR,
+            severity: Severity::Error,
+            file: file.to_owned(),
+            location: error.location,
+            msg: error.msg,
+            help: None,
+        }
+    }
+}
+
+pub const PARSE_ERROR: &str = "parse-error";
+
+pub trait LintRule {
+    /// A unique kebab-case name, used to enable or disable the rule.
+    fn name(&self) -> &'static str;
+
+    fn description(&self) -> &'static str;
+
+    fn enabled_by_default(&self) -> bool {
+        true
+    }
+
+    /// Checks all documents at once, so the rules can look for references between files.
+    fn check(&self, documents: &[LintDocument], lints: &mut Vec<Lint>);
+}
+
+/// Returns all rules provided by the crate.
+pub fn rules() -> Vec<Box<dyn LintRule>> {
+    vec![
+        Box::new(DuplicateFlow),
+        Box::new(UndefinedPublicFlow),
+        Box::new(UndefinedCall),
+        Box::new(UnreachableFlow),
+        Box::new(UndefinedVariable),
+    ]
+}
+
+/// Runs the rules and returns the lints sorted by file and location.
+pub fn run(rules: &[&dyn LintRule], documents: &[LintDocument]) -> Vec<Lint> {
+    let mut lints = Vec::new();
+    for rule in rules {
+        rule.check(documents, &mut lints);
+    }
+    lints.sort_by_key(|lint| {
+        let start = lint.location.as_ref().map(|l| l.start.index);
+        (lint.file.clone(), start)
+    });
+    lints
+}
+
+/// Returns the top-level flows (i.e. not the ones defined in profiles) of all documents.
+pub fn flows<'a>(documents: &'a [LintDocument<'a>]) -> impl Iterator<Item = (&'a str, &'a Flow)> {
+    documents
+        .iter()
+        .flat_map(|doc| doc.document.flows.iter().flatten().map(|flow| (doc.file, flow)))
+}
+
+fn position_str(file: &str, location: &Location) -> String {
+    format!("{file}:{}:{}", location.start.line, location.start.col + 1)
+}
+
+/// Flows with the same name: the last definition wins, which is rarely intended.
+pub struct DuplicateFlow;
+
+impl LintRule for DuplicateFlow {
+    fn name(&self) -> &'static str {
+        "duplicate-flow"
+    }
+
+    fn description(&self) -> &'static str {
+        "A flow is defined more than once"
+    }
+
+    fn check(&self, documents: &[LintDocument], lints: &mut Vec<Lint>) {
+        let mut seen: HashMap<&str, (&str, &Location)> = HashMap::new();
+        for (file, flow) in flows(documents) {
+            if let Some((prev_file, prev_location)) = seen.get(flow.name.as_str()) {
+                lints.push(Lint {
+                    rule: self.name(),
+                    severity: Severity::Warning,
+                    file: file.to_owned(),
+                    location: Some(flow.location.clone()),
+                    msg: format!("Flow '{}' is already defined", flow.name),
+                    help: Some(format!(
+                        "the previous definition is at {}",
+                        position_str(prev_file, prev_location)
+                    )),
+                });
+            } else {
+                seen.insert(&flow.name, (file, &flow.location));
+            }
+        }
+    }
+}
+
+pub struct UndefinedPublicFlow;
+
+impl LintRule for UndefinedPublicFlow {
+    fn name(&self) -> &'static str {
+        "undefined-public-flow"
+    }
+
+    fn description(&self) -> &'static str {
+        "A flow listed in 'publicFlows' is not defined"
+    }
+
+    fn check(&self, documents: &[LintDocument], lints: &mut Vec<Lint>) {
+        let defined: Vec<&str> = flows(documents).map(|(_, flow)| flow.name.as_str()).collect();
+        for doc in documents {
+            for name in doc.document.public_flows.iter().flatten() {
+                if !defined.contains(&name.value.as_str()) {
+                    lints.push(Lint {
+                        rule: self.name(),
+                        severity: Severity::Error,
+                        file: doc.file.to_owned(),
+                        location: Some(name.location.clone()),
+                        msg: format!("Public flow '{}' is not defined", name.value),
+                        help: None,
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// `call` steps with unknown flow names. The calls with expressions in the flow name are skipped.
+pub struct UndefinedCall;
+
+impl LintRule for UndefinedCall {
+    fn name(&self) -> &'static str {
+        "undefined-flow"
+    }
+
+    fn description(&self) -> &'static str {
+        "A 'call' step refers to a flow that is not defined"
+    }
+
+    fn check(&self, documents: &[LintDocument], lints: &mut Vec<Lint>) {
+        let defined: Vec<&str> = documents
+            .iter()
+            .flat_map(|doc| all_flows(doc.document))
+            .map(|flow| flow.name.as_str())
+            .collect();
+        for doc in documents {
+            for undefined in undefined_flows(doc.document, &defined) {
+                lints.push(Lint {
+                    rule: self.name(),
+                    severity: Severity::Error,
+                    file: doc.file.to_owned(),
+                    location: Some(undefined.location),
+                    msg: format!("Flow '{}' is not defined", undefined.flow_name),
+                    help: undefined.suggestion.map(|s| format!("did you mean '{s}'?")),
+                });
+            }
+        }
+    }
+}
+
+/// Flows that are not reachable from the entry points. Disabled by default: all files of the project must be
+/// checked together to avoid false positives.
+pub struct UnreachableFlow;
+
+impl LintRule for UnreachableFlow {
+    fn name(&self) -> &'static str {
+        "unreachable-flow"
+    }
+
+    fn description(&self) -> &'static str {
+        "A flow is never called from the entry points (public flows, the entry point and triggers)"
+    }
+
+    fn enabled_by_default(&self) -> bool {
+        false
+    }
+
+    fn check(&self, documents: &[LintDocument], lints: &mut Vec<Lint>) {
+        let models: Vec<&ConcordDocument> = documents.iter().map(|doc| doc.document).collect();
+        for flow in unreachable_flows(&models) {
+            let (severity, msg, help) = match &flow.dynamic_call {
+                None => (
+                    Severity::Error,
+                    format!("Flow '{}' is never called", flow.name),
+                    None,
+                ),
+                Some(call) => (
+                    Severity::Warning,
+                    format!("Flow '{}' is never called directly", flow.name),
+                    Some(format!(
+                        "it might be called by the dynamic 'call' step at {}:{}",
+                        call.start.line,
+                        call.start.col + 1
+                    )),
+                ),
+            };
+            lints.push(Lint {
+                rule: self.name(),
+                severity,
+                file: documents[flow.document].file.to_owned(),
+                location: Some(flow.location),
+                msg,
+                help,
+            });
+        }
+    }
+}
+
+/// Variables that are read but never set. Disabled by default: variables passed when the process is started
+/// or set by scripts can't be known.
+pub struct UndefinedVariable;
+
+impl LintRule for UndefinedVariable {
+    fn name(&self) -> &'static str {
+        "undefined-variable"
+    }
+
+    fn description(&self) -> &'static str {
+        "An expression reads a variable that is never set by the flow, its callers or the arguments"
+    }
+
+    fn enabled_by_default(&self) -> bool {
+        false
+    }
+
+    fn check(&self, documents: &[LintDocument], lints: &mut Vec<Lint>) {
+        let models: Vec<(&str, &ConcordDocument)> =
+            documents.iter().map(|doc| (doc.source, doc.document)).collect();
+        for variable in undefined_variables(&models) {
+            lints.push(Lint {
+                rule: self.name(),
+                severity: Severity::Warning,
+                file: documents[variable.document].file.to_owned(),
+                location: Some(variable.location),
+                msg: format!(
+                    "Variable '{}' is never set in flow '{}' or the flows calling it",
+                    variable.name, variable.flow
+                ),
+                help: variable.suggestion.map(|s| format!("did you mean '{s}'?")),
+            });
+        }
+    }
+}
+
+fn json_option(value: Option<&str>) -> String {
+    value.map(double_quoted).unwrap_or_else(|| "null".to_owned())
+}
+
+/// Renders the lints as a JSON array. Lines and columns are 1-based, the end is exclusive:
+/// `[{"rule": "...", "severity": "warning", "file": "...", "line": 1, "column": 1, "endLine": 1,
+/// "endColumn": 5, "message": "...", "help": null}]`.
+pub fn to_json(lints: &[Lint]) -> String {
+    let items: Vec<String> = lints
+        .iter()
+        .map(|lint| {
+            let span = match &lint.location {
+                Some(l) => format!(
+                    "\"line\": {}, \"column\": {}, \"endLine\": {}, \"endColumn\": {}",
+                    l.start.line,
+                    l.start.col + 1,
+                    l.end.line,
+                    l.end.col + 1
+                ),
+                None => "\"line\": null, \"column\": null, \"endLine\": null, \"endColumn\": null".to_owned(),
+            };
+            format!(
+                "  {{\"rule\": {}, \"severity\": {}, \"file\": {}, {span}, \"message\": {}, \"help\": {}}}",
+                double_quoted(lint.rule),
+                double_quoted(lint.severity.as_str()),
+                double_quoted(&lint.file),
+                double_quoted(&lint.msg),
+                json_option(lint.help.as_deref())
+            )
+        })
+        .collect();
+    if items.is_empty() {
+        "[]\n".to_owned()
+    } else {
+        format!("[\n{}\n]\n", items.join(",\n"))
+    }
+}
+
+/// Renders the lints as a [SARIF 2.1.0](https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html)
+/// log, understood by code scanning tools.
+pub fn to_sarif(lints: &[Lint], rules: &[&dyn LintRule]) -> String {
+    let mut rule_items: Vec<String> = vec![format!(
+        "{{\"id\": {}, \"shortDescription\": {{\"text\": \"The file can't be parsed\"}}}}",
+        double_quoted(PARSE_ERROR)
+    )];
+    rule_items.extend(rules.iter().map(|rule| {
+        format!(
+            "{{\"id\": {}, \"shortDescription\": {{\"text\": {}}}}}",
+            double_quoted(rule.name()),
+            double_quoted(rule.description())
+        )
+    }));
+
+    let results: Vec<String> = lints
+        .iter()
+        .map(|lint| {
+            let mut region = String::new();
+            if let Some(l) = &lint.location {
+                region = format!(
+                    concat!(
+                        ", \"region\": {{\"startLine\": {}, \"startColumn\": {}, ",
+                        "\"endLine\": {}, \"endColumn\": {}}}"
+                    ),
+                    l.start.line,
+                    l.start.col + 1,
+                    l.end.line,
+                    l.end.col + 1
+                );
+            }
+            let mut text = lint.msg.clone();
+            if let Some(help) = &lint.help {
+                text.push_str(&format!(" ({help})"));
+            }
+            format!(
+                concat!(
+                    "{{\"ruleId\": {}, \"level\": {}, \"message\": {{\"text\": {}}}, ",
+                    "\"locations\": [{{\"physicalLocation\": ",
+                    "{{\"artifactLocation\": {{\"uri\": {}}}{}}}}}]}}"
+                ),
+                double_quoted(lint.rule),
+                double_quoted(lint.severity.as_str()),
+                double_quoted(&text),
+                double_quoted(&lint.file),
+                region
+            )
+        })
+        .collect();
+
+    format!(
+        concat!(
+            "{{\n",
+            "  \"$schema\": \"https://json.schemastore.org/sarif-2.1.0.json\",\n",
+            "  \"version\": \"2.1.0\",\n",
+            "  \"runs\": [{{\n",
+            "    \"tool\": {{\"driver\": {{\"name\": \"concord-lint\", \"rules\": [\n",
+            "      {}\n",
+            "    ]}}}},\n",
+            "    \"results\": [{}]\n",
+            "  }}]\n",
+            "}}\n"
+        ),
+        rule_items.join(",\n      "),
+        if results.is_empty() {
+            String::new()
+        } else {
+            format!("\n      {}\n    ", results.join(",\n      "))
+        }
+    )
+}
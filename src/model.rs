@@ -1,30 +1,56 @@
 use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
 
+/// The path of a node in the document (e.g. `document->flows->'main' flow`) together with the identifier of
+/// the source the document was read from, usually a file name (see `Input::with_source_id`).
 #[derive(Default, Clone)]
-pub struct DocumentPath(Vec<String>);
+pub struct DocumentPath {
+    source_id: Option<Arc<str>>,
+    path: Vec<String>,
+}
 
 impl DocumentPath {
     pub fn new(value: &[String]) -> Self {
-        Self(Vec::from(value))
+        Self {
+            source_id: None,
+            path: Vec::from(value),
+        }
     }
 
     pub fn none() -> Self {
-        Self(vec!["n/a".to_owned()])
+        Self::new(&["n/a".to_owned()])
+    }
+
+    pub fn with_source_id(mut self, source_id: Option<Arc<str>>) -> Self {
+        self.source_id = source_id;
+        self
+    }
+
+    pub fn source_id(&self) -> Option<&str> {
+        self.source_id.as_deref()
+    }
+}
+
+// serialized as the list of path elements, the source id is serialized next to it by [Location]
+#[cfg(feature = "serde")]
+impl serde::Serialize for DocumentPath {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.path.serialize(serializer)
     }
 }
 
 impl Debug for DocumentPath {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let mut i = 0;
-        let len = self.0.len();
+        let len = self.path.len();
         loop {
             if i >= len {
                 break;
             }
             if i + 1 < len {
-                write!(f, "{}->", self.0[i])?;
+                write!(f, "{}->", self.path[i])?;
             } else {
-                write!(f, "{}", self.0[i])?;
+                write!(f, "{}", self.path[i])?;
             }
 
             i += 1;
@@ -33,15 +59,54 @@ impl Debug for DocumentPath {
     }
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "camelCase"))]
+pub struct Position {
+    pub index: usize, // byte offset
+    pub line: usize,  // 1-based
+    pub col: usize,   // 0-based, in chars
+}
+
+/// The span of a node in the source text. The `end` position is exclusive.
+///
+/// With the `serde` feature enabled, serialized as `{"path": [...], "sourceId": ..., "start": ..., "end": ...}`.
 #[derive(Debug, Clone)]
 pub struct Location {
     pub path: DocumentPath,
-    pub index: usize,
-    pub line: usize,
-    pub col: usize,
+    pub start: Position,
+    pub end: Position,
 }
 
-#[derive(Debug)]
+// the documents of a project come from different files, so each location carries its source id
+#[cfg(feature = "serde")]
+impl serde::Serialize for Location {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut location = serializer.serialize_struct("Location", 4)?;
+        location.serialize_field("path", &self.path)?;
+        location.serialize_field("sourceId", &self.source_id())?;
+        location.serialize_field("start", &self.start)?;
+        location.serialize_field("end", &self.end)?;
+        location.end()
+    }
+}
+
+impl Location {
+    /// The identifier of the source (e.g. the file name) the location is in, if known.
+    pub fn source_id(&self) -> Option<&str> {
+        self.path.source_id()
+    }
+}
+
+/// With the `serde` feature enabled, serialized as `{"type": "<variant>", "value": ...}` where the type is one of
+/// `string`, `boolean`, `float`, `integer`, `array` or `mapping`. Floats are serialized as strings and
+/// mappings as arrays of [KV] to preserve the order of keys and their locations.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(tag = "type", content = "value", rename_all = "camelCase")
+)]
 pub enum Value {
     String(String),
     Boolean(bool),
@@ -51,20 +116,23 @@ pub enum Value {
     Mapping(Vec<KV>),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "camelCase"))]
 pub struct KV {
     pub location: Location,
     pub key: String,
     pub value: Value,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "camelCase"))]
 pub enum LoopMode {
     Serial,
     Parallel,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "camelCase"))]
 pub struct Loop {
     pub location: Location,
     pub items: Value,
@@ -72,7 +140,8 @@ pub struct Loop {
     pub parallelism: Option<Value>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "camelCase"))]
 pub struct Retry {
     pub location: Location,
     pub times: Option<Value>,
@@ -80,19 +149,52 @@ pub struct Retry {
     pub input: Option<Value>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "camelCase"))]
 pub struct SwitchCase {
     pub label: Value,
     pub steps: Vec<FlowStep>,
 }
 
-#[derive(Debug)]
+/// A value together with the location of its key, or of the value itself for list items.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "camelCase"))]
+pub struct Located<T> {
+    pub location: Location,
+    pub value: T,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "camelCase"))]
 pub struct Configuration {
     pub location: Location,
-    pub values: Vec<KV>,
+    pub runtime: Option<Located<String>>,
+    pub entry_point: Option<Located<String>>,
+    pub dependencies: Option<Located<Vec<String>>>,
+    pub extra_dependencies: Option<Located<Vec<String>>>,
+    pub arguments: Option<Located<Vec<KV>>>,
+    pub debug: Option<Located<bool>>,
+    pub meta: Option<Located<Vec<KV>>>,
+    pub events: Option<Located<Vec<KV>>>,
+    pub requirements: Option<Located<Vec<KV>>>,
+    pub process_timeout: Option<Located<String>>, // ISO 8601 duration, e.g. PT1H
+    pub suspend_timeout: Option<Located<String>>,
+    pub exclusive: Option<Exclusive>,
+    pub out: Option<Located<Vec<String>>>,
+    pub template: Option<Located<String>>,
+    pub parallel_loop_parallelism: Option<Located<i64>>,
+    pub extra: Vec<KV>, // non-standard parameters, kept verbatim
 }
 
-#[derive(Debug)]
+/// With the `serde` feature enabled, serialized as an object with the `type` tag (the variant's name in camelCase,
+/// e.g. `taskCall` or `return`) and the variant's fields in camelCase:
+/// `{"type": "flowCall", "flowName": "myFlow", "input": null, ...}`.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")
+)]
 #[allow(clippy::large_enum_variant)]
 pub enum StepDefinition {
     TaskCall {
@@ -178,38 +280,236 @@ pub enum StepDefinition {
     Return,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "camelCase"))]
 pub struct FlowStep {
     pub location: Location,
     pub step_name: Option<String>,
     pub step: StepDefinition,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "camelCase"))]
 pub struct Flow {
     pub location: Location,
     pub name: String,
     pub steps: Vec<FlowStep>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "camelCase"))]
+pub enum FormFieldType {
+    String,
+    Int,
+    Decimal,
+    Boolean,
+    File,
+    Date,
+    DateTime,
+}
+
+/// Derived from the suffix of the field's type: none, `?`, `+` or `*`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "camelCase"))]
+pub enum Cardinality {
+    OneAndOnlyOne,
+    OneOrNone,
+    AtLeastOne,
+    Any,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "camelCase"))]
+pub struct FormFieldSpec {
+    pub location: Location,
+    pub field_type: FormFieldType,
+    pub cardinality: Cardinality,
+    pub label: Option<String>,
+    pub value: Option<Value>,
+    pub allow: Option<Value>,
+    pub min: Option<Value>,
+    pub max: Option<Value>,
+    pub pattern: Option<String>,
+    pub placeholder: Option<String>,
+    pub readonly: Option<bool>,
+    pub extra: Vec<KV>, // non-standard options (e.g. `inputType`), kept verbatim
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "camelCase"))]
 pub struct FormField {
     pub location: Location,
     pub name: String,
-    pub options: Vec<KV>,
+    pub spec: FormFieldSpec,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "camelCase"))]
 pub struct Form {
     pub location: Location,
     pub name: String,
     pub fields: Vec<FormField>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "camelCase"))]
+pub enum ExclusiveMode {
+    Cancel,
+    CancelOld,
+    Wait,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "camelCase"))]
+pub struct Exclusive {
+    pub location: Location,
+    pub group: String,
+    pub mode: Option<ExclusiveMode>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")
+)]
+pub enum TriggerDefinition {
+    #[cfg_attr(feature = "serde", serde(rename = "github"))]
+    GitHub {
+        version: Option<i64>,
+        use_initiator: Option<bool>,
+        use_event_commit_id: Option<bool>,
+        ignore_empty_push: Option<bool>,
+        entry_point: String,
+        active_profiles: Option<Vec<String>>,
+        arguments: Option<Vec<KV>>,
+        conditions: Option<Vec<KV>>,
+        exclusive: Option<Exclusive>,
+    },
+    Cron {
+        spec: String,
+        timezone: Option<String>,
+        run_as: Option<Value>,
+        entry_point: String,
+        active_profiles: Option<Vec<String>>,
+        arguments: Option<Vec<KV>>,
+        exclusive: Option<Exclusive>,
+    },
+    Manual {
+        name: Option<String>,
+        entry_point: String,
+        active_profiles: Option<Vec<String>>,
+        arguments: Option<Vec<KV>>,
+    },
+    #[cfg_attr(feature = "serde", serde(rename = "oneops"))]
+    OneOps {
+        use_initiator: Option<bool>,
+        entry_point: String,
+        active_profiles: Option<Vec<String>>,
+        arguments: Option<Vec<KV>>,
+        conditions: Option<Vec<KV>>,
+        exclusive: Option<Exclusive>,
+    },
+    Generic {
+        version: Option<i64>,
+        entry_point: String,
+        active_profiles: Option<Vec<String>>,
+        arguments: Option<Vec<KV>>,
+        conditions: Option<Vec<KV>>,
+        exclusive: Option<Exclusive>,
+    },
+    Unknown {
+        event_source: String,
+        entry_point: Option<String>,
+        active_profiles: Option<Vec<String>>,
+        arguments: Option<Vec<KV>>,
+        conditions: Option<Vec<KV>>,
+        exclusive: Option<Exclusive>,
+        options: Vec<KV>, // anything else, kept verbatim
+    },
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "camelCase"))]
+pub struct Trigger {
+    pub location: Location,
+    pub trigger: TriggerDefinition,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "camelCase"))]
+pub struct ImportSecret {
+    pub location: Location,
+    pub org: Option<String>,
+    pub name: String,
+    pub password: Option<Value>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")
+)]
+#[allow(clippy::large_enum_variant)]
+pub enum Import {
+    Git {
+        location: Location,
+        url: Option<String>,
+        name: Option<String>,
+        version: Option<String>,
+        path: Option<String>,
+        dest: Option<String>,
+        exclude: Option<Vec<String>>,
+        secret: Option<ImportSecret>,
+    },
+    Mvn {
+        location: Location,
+        url: String,
+        dest: Option<String>,
+    },
+    Dir {
+        location: Location,
+        src: String,
+        dest: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "camelCase"))]
+pub struct Profile {
+    pub location: Location,
+    pub name: String,
+    pub configuration: Option<Configuration>,
+    pub flows: Option<Vec<Flow>>,
+    pub forms: Option<Vec<Form>>,
+}
+
+/// A list of patterns (`glob:...` or `regex:...`). The plain list form only specifies includes.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "camelCase"))]
+pub struct ResourcePatterns {
+    pub location: Location,
+    pub includes: Vec<String>,
+    pub excludes: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "camelCase"))]
+pub struct Resources {
+    pub location: Location,
+    pub concord: Option<ResourcePatterns>,
+}
+
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "camelCase"))]
 pub struct ConcordDocument {
     pub configuration: Option<Configuration>,
     pub flows: Option<Vec<Flow>>,
     pub forms: Option<Vec<Form>>,
-    pub public_flows: Option<Vec<String>>,
+    pub public_flows: Option<Vec<Located<String>>>,
+    pub triggers: Option<Vec<Trigger>>,
+    pub imports: Option<Vec<Import>>,
+    pub profiles: Option<Vec<Profile>>,
+    pub resources: Option<Resources>,
 }
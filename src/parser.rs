@@ -1,8 +1,9 @@
 use crate::error::{ErrorKind, ParseError};
 use crate::input::{next_kv, Event, Input, Marker};
 use crate::model::{
-    ConcordDocument, Configuration, Flow, FlowStep, Form, FormField, Loop, LoopMode, Retry, StepDefinition,
-    SwitchCase, Value, KV,
+    Cardinality, ConcordDocument, Configuration, Exclusive, ExclusiveMode, Flow, FlowStep, Form, FormField,
+    FormFieldSpec, FormFieldType, Import, ImportSecret, Located, Location, Loop, LoopMode, Profile,
+    ResourcePatterns, Resources, Retry, StepDefinition, SwitchCase, Trigger, TriggerDefinition, Value, KV,
 };
 use crate::parse_until;
 
@@ -15,13 +16,24 @@ fn parse_bool<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<bool, Pa
     match input.next_value()? {
         (Value::Boolean(result), ..) => Ok(result),
         (value, marker) => Err(ParseError {
-            location: Some((input.current_document_path(), marker).into()),
+            location: Some(input.location(&marker)),
             kind: ErrorKind::UnexpectedSyntax,
             msg: format!("Expected a bool value, got '{value:?}"),
         }),
     }
 }
 
+fn parse_i64<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<i64, ParseError> {
+    match input.next_value()? {
+        (Value::Integer(result), ..) => Ok(result),
+        (value, marker) => Err(ParseError {
+            location: Some(input.location(&marker)),
+            kind: ErrorKind::UnexpectedSyntax,
+            msg: format!("Expected an integer value, got '{value:?}"),
+        }),
+    }
+}
+
 fn parse_string<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<String, ParseError> {
     let (value, _) = input.next_string()?;
     Ok(value)
@@ -34,28 +46,148 @@ fn parse_list_of_strings<T: Iterator<Item = char>>(input: &mut Input<T>) -> Resu
     Ok(values)
 }
 
+fn parse_located_string<T: Iterator<Item = char>>(
+    input: &mut Input<T>,
+) -> Result<Located<String>, ParseError> {
+    let (value, marker) = input.next_string()?;
+    Ok(Located {
+        location: input.location_since(&marker),
+        value,
+    })
+}
+
+fn parse_list_of_located_strings<T: Iterator<Item = char>>(
+    input: &mut Input<T>,
+) -> Result<Vec<Located<String>>, ParseError> {
+    input.next_sequence_start()?;
+    let values = parse_until!(input, Event::SequenceEnd, parse_located_string);
+    input.next_sequence_end()?;
+    Ok(values)
+}
+
+/// The form field types accepted by [parse_form_field_type], as listed in its error message.
+const FORM_FIELD_TYPES: &str = "'string', 'int', 'decimal', 'boolean', 'file', 'date' or 'dateTime'";
+
+fn parse_form_field_type<T: Iterator<Item = char>>(
+    input: &mut Input<T>,
+) -> Result<(FormFieldType, Cardinality), ParseError> {
+    let (value, marker) = input.next_string()?;
+
+    let (type_name, cardinality) = match value.char_indices().last() {
+        Some((idx, '?')) => (&value[..idx], Cardinality::OneOrNone),
+        Some((idx, '+')) => (&value[..idx], Cardinality::AtLeastOne),
+        Some((idx, '*')) => (&value[..idx], Cardinality::Any),
+        _ => (value.as_str(), Cardinality::OneAndOnlyOne),
+    };
+
+    let field_type = match type_name {
+        "string" => FormFieldType::String,
+        "int" => FormFieldType::Int,
+        "decimal" => FormFieldType::Decimal,
+        "boolean" => FormFieldType::Boolean,
+        "file" => FormFieldType::File,
+        "date" => FormFieldType::Date,
+        "dateTime" => FormFieldType::DateTime,
+        _ => {
+            return Err(ParseError {
+                location: Some(input.location(&marker)),
+                kind: ErrorKind::UnexpectedSyntax,
+                msg: format!(
+                    "Invalid form field type '{value}'. Expected one of {FORM_FIELD_TYPES}, \
+                     optionally followed by '?', '+' or '*'."
+                ),
+            })
+        }
+    };
+
+    Ok((field_type, cardinality))
+}
+
+fn parse_form_field_spec<T: Iterator<Item = char>>(
+    input: &mut Input<T>,
+) -> Result<FormFieldSpec, ParseError> {
+    let (_, marker) = input.next_mapping_start()?;
+
+    let mut field_type = None;
+    let mut label = None;
+    let mut value = None;
+    let mut allow = None;
+    let mut min = None;
+    let mut max = None;
+    let mut pattern = None;
+    let mut placeholder = None;
+    let mut readonly = None;
+    let mut extra = Vec::new();
+
+    while let Ok(Some((element, key_marker))) = input.peek_string() {
+        input.try_next()?;
+        match element.as_str() {
+            "type" => field_type = Some(input.with_context("'type' option", parse_form_field_type)?),
+            "label" => label = Some(input.with_context("'label' option", parse_string)?),
+            "value" => value = Some(input.with_context("'value' option", parse_value)?),
+            "allow" => allow = Some(input.with_context("'allow' option", parse_value)?),
+            "min" => min = Some(input.with_context("'min' option", parse_value)?),
+            "max" => max = Some(input.with_context("'max' option", parse_value)?),
+            "pattern" => pattern = Some(input.with_context("'pattern' option", parse_string)?),
+            "placeholder" => placeholder = Some(input.with_context("'placeholder' option", parse_string)?),
+            "readonly" => readonly = Some(input.with_context("'readonly' option", parse_bool)?),
+            _ => {
+                let value = input.with_context(format!("'{element}'"), parse_value)?;
+                extra.push(KV {
+                    location: input.location_since(&key_marker),
+                    key: element,
+                    value,
+                });
+            }
+        }
+    }
+    input.next_mapping_end()?;
+    let location = input.location_since(&marker);
+
+    let Some((field_type, cardinality)) = field_type else {
+        return Err(ParseError {
+            location: Some(location),
+            kind: ErrorKind::UnexpectedSyntax,
+            msg: "The 'type' field is required in the form field".to_owned(),
+        });
+    };
+
+    Ok(FormFieldSpec {
+        location,
+        field_type,
+        cardinality,
+        label,
+        value,
+        allow,
+        min,
+        max,
+        pattern,
+        placeholder,
+        readonly,
+        extra,
+    })
+}
+
 fn parse_form_field<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<FormField, ParseError> {
     input.next_mapping_start()?;
 
     let (name, marker) = input.next_string()?;
     input.enter_context(format!("'{name}' field"));
 
-    input.next_mapping_start()?;
-    let options = parse_until!(input, Event::MappingEnd, next_kv);
-    input.next_mapping_end()?;
+    let spec = parse_form_field_spec(input)?;
 
     input.next_mapping_end()?;
     input.leave_context();
     Ok(FormField {
-        location: (input.current_document_path(), marker).into(),
+        location: input.location_since(&marker),
         name,
-        options,
+        spec,
     })
 }
 
 fn parse_form_fields<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Vec<FormField>, ParseError> {
     input.next_sequence_start()?;
-    let fields = parse_until!(input, Event::SequenceEnd, parse_form_field);
+    let fields = parse_until!(input, Event::SequenceEnd, parse_form_field, 1);
     input.next_sequence_end()?;
     Ok(fields)
 }
@@ -66,7 +198,7 @@ fn parse_form<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Form, Pa
     let fields = parse_form_fields(input)?;
     input.leave_context();
     Ok(Form {
-        location: (input.current_document_path(), marker).into(),
+        location: input.location_since(&marker),
         name,
         fields,
     })
@@ -74,7 +206,7 @@ fn parse_form<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Form, Pa
 
 fn parse_forms<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Vec<Form>, ParseError> {
     input.next_mapping_start()?;
-    let result = parse_until!(input, Event::MappingEnd, parse_form);
+    let result = parse_until!(input, Event::MappingEnd, parse_form, 2);
     input.next_mapping_end()?;
     Ok(result)
 }
@@ -92,7 +224,7 @@ fn parse_loop_mode<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Loo
         "parallel" => Ok(LoopMode::Parallel),
         "serial" => Ok(LoopMode::Serial),
         unknown => Err(ParseError {
-            location: Some((input.current_document_path(), marker).into()),
+            location: Some(input.location(&marker)),
             kind: ErrorKind::UnexpectedSyntax,
             msg: format!("Unexpected loop mode '{unknown}'. Only 'parallel' and 'serial' are supported."),
         }),
@@ -102,7 +234,7 @@ fn parse_loop_mode<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Loo
 fn parse_loop<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Loop, ParseError> {
     let (_, marker) = input.next_mapping_start()?;
 
-    let location = (input.current_document_path(), marker).into();
+    let location = input.location(&marker);
     let mut items = None;
     let mut mode = None;
     let mut parallelism = None;
@@ -123,6 +255,7 @@ fn parse_loop<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Loop, Pa
         }
     }
     input.next_mapping_end()?;
+    let location = input.location_since(&marker);
 
     let Some(items) = items else {
         return Err(ParseError {
@@ -143,7 +276,7 @@ fn parse_loop<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Loop, Pa
 fn parse_retry<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Retry, ParseError> {
     let (_, marker) = input.next_mapping_start()?;
 
-    let location = (input.current_document_path(), marker).into();
+    let location = input.location(&marker);
     let mut times = None;
     let mut delay = None;
     let mut retry_input = None;
@@ -164,6 +297,7 @@ fn parse_retry<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Retry,
         }
     }
     input.next_mapping_end()?;
+    let location = input.location_since(&marker);
 
     Ok(Retry {
         location,
@@ -177,7 +311,7 @@ fn parse_task_call<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Ste
     let (task_name, marker) = input.next_string()?;
     input.enter_context(format!("'{task_name}' task call"));
 
-    let location = (input.current_document_path(), marker).into();
+    let location = input.location(&marker);
     let mut task_input = None;
     let mut task_output = None;
     let mut error = None;
@@ -231,7 +365,7 @@ fn parse_simple_task_call<T: Iterator<Item = char>>(
     input.enter_context(task_name);
 
     let (value, marker) = input.next_value()?;
-    let location = (input.current_document_path(), marker).into();
+    let location = input.location_since(&marker);
     let mut meta = None;
 
     while let Ok(Some((element, _))) = input.peek_string() {
@@ -299,7 +433,7 @@ fn parse_expr<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<StepDefi
     let (expr, marker) = input.next_string()?;
     input.enter_context(format!("expression '{expr}'"));
 
-    let location = (input.current_document_path(), marker).into();
+    let location = input.location(&marker);
     let mut expr_output = None;
     let mut error = None;
     let mut meta = None;
@@ -336,7 +470,7 @@ fn parse_script<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<StepDe
     let (language_or_ref, marker) = input.next_string()?;
     input.enter_context(format!("script '{language_or_ref}"));
 
-    let location = (input.current_document_path(), marker).into();
+    let location = input.location(&marker);
     let mut body = None;
     let mut script_input = None;
     let mut script_output = None;
@@ -385,7 +519,7 @@ fn parse_flow_call<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Ste
     let (flow_name, marker) = input.next_string()?;
     input.enter_context(format!("call '{flow_name}"));
 
-    let location = (input.current_document_path(), marker).into();
+    let location = input.location(&marker);
     let mut call_input = None;
     let mut call_output = None;
     let mut error = None;
@@ -431,7 +565,7 @@ fn parse_checkpoint<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<St
     let (name, marker) = input.next_string()?;
     input.enter_context(format!("checkpoint '{name}"));
 
-    let location = (input.current_document_path(), marker).into();
+    let location = input.location(&marker);
     let mut meta = None;
 
     while let Ok(Some((element, _))) = input.peek_string() {
@@ -457,7 +591,7 @@ fn parse_if<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<StepDefini
     let (expression, marker) = input.next_string()?;
     input.enter_context(format!("if '{expression}"));
 
-    let location = (input.current_document_path(), marker).into();
+    let location = input.location(&marker);
     let mut then_steps = None;
     let mut else_steps = None;
     let mut meta = None;
@@ -505,7 +639,7 @@ fn parse_set_variables<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result
     let vars = parse_until!(input, Event::MappingEnd, next_kv);
     input.next_mapping_end()?;
 
-    let location = (input.current_document_path(), marker).into();
+    let location = input.location(&marker);
     let mut meta = None;
 
     while let Ok(Some((element, _))) = input.peek_string() {
@@ -534,7 +668,7 @@ fn parse_parallel_block<T: Iterator<Item = char>>(
 
     let (steps, marker) = parse_flow_steps(input)?;
 
-    let location = (input.current_document_path(), marker).into();
+    let location = input.location(&marker);
     let mut block_output = None;
     let mut meta = None;
 
@@ -567,7 +701,7 @@ fn parse_block<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<StepDef
 
     let (steps, marker) = parse_flow_steps(input)?;
 
-    let location = (input.current_document_path(), marker).into();
+    let location = input.location(&marker);
     let mut block_output = None;
     let mut error = None;
     let mut looping = None;
@@ -607,7 +741,7 @@ fn parse_switch<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<StepDe
     let (expression, marker) = input.next_string()?;
     input.enter_context(format!("switch '{expression}'"));
 
-    let location = (input.current_document_path(), marker).into();
+    let location = input.location(&marker);
     let mut cases = Vec::new();
     let mut default = None;
     let mut meta = None;
@@ -658,7 +792,7 @@ fn parse_suspend<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<StepD
 
     input.enter_context(format!("suspend on '{event}'"));
 
-    let location = (input.current_document_path(), marker).into();
+    let location = input.location(&marker);
     let mut meta = None;
 
     while let Ok(Some((element, _))) = input.peek_string() {
@@ -685,7 +819,7 @@ fn parse_form_call<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Ste
 
     input.enter_context(format!("'{form_name}' form call"));
 
-    let location = (input.current_document_path(), marker).into();
+    let location = input.location(&marker);
     let mut yield_execution = None;
     let mut save_submitted_by = None;
     let mut run_as = None;
@@ -731,7 +865,7 @@ fn parse_flow_step<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Flo
     // "scalar" steps
     if let Ok(Some((scalar, step_marker))) = input.peek_string() {
         input.try_next()?;
-        let location = (input.current_document_path(), step_marker).into();
+        let location = input.location_since(&step_marker);
         return match scalar.as_str() {
             "return" => Ok(FlowStep {
                 location,
@@ -749,11 +883,10 @@ fn parse_flow_step<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Flo
     // "object" steps
     let (_, step_marker) = input.next_mapping_start()?;
 
-    let location = (input.current_document_path(), step_marker).into();
     let mut step_name = None;
     let mut step = None;
 
-    while let Ok(Some((name_or_step, ..))) = input.peek_string() {
+    while let Ok(Some((name_or_step, key_marker))) = input.peek_string() {
         input.try_next()?;
         match name_or_step.as_str() {
             "name" => step_name = Some(input.next_string()?.0),
@@ -774,7 +907,7 @@ fn parse_flow_step<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Flo
             "form" => step = Some(parse_form_call(input)?),
             unknown => {
                 return Err(ParseError {
-                    location: Some(location),
+                    location: Some(input.location(&key_marker)),
                     kind: ErrorKind::UnexpectedSyntax,
                     msg: format!("Unknown step '{unknown}'"),
                 })
@@ -783,6 +916,7 @@ fn parse_flow_step<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Flo
     }
 
     input.next_mapping_end()?;
+    let location = input.location_since(&step_marker);
 
     let Some(step) = step else {
         return Err(ParseError {
@@ -803,7 +937,7 @@ fn parse_flow_steps<T: Iterator<Item = char>>(
     input: &mut Input<T>,
 ) -> Result<(Vec<FlowStep>, Marker), ParseError> {
     let (_, marker) = input.next_sequence_start()?;
-    let steps = parse_until!(input, Event::SequenceEnd, parse_flow_step);
+    let steps = parse_until!(input, Event::SequenceEnd, parse_flow_step, 1);
     input.next_sequence_end()?;
     Ok((steps, marker))
 }
@@ -814,7 +948,7 @@ fn parse_flow<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Flow, Pa
     let (steps, _) = parse_flow_steps(input)?;
     input.leave_context();
     Ok(Flow {
-        location: (input.current_document_path(), marker).into(),
+        location: input.location_since(&marker),
         name,
         steps,
     })
@@ -822,49 +956,823 @@ fn parse_flow<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Flow, Pa
 
 fn parse_flows<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Vec<Flow>, ParseError> {
     input.next_mapping_start()?;
-    let result = parse_until!(input, Event::MappingEnd, parse_flow);
+    let result = parse_until!(input, Event::MappingEnd, parse_flow, 2);
     input.next_mapping_end()?;
     Ok(result)
 }
 
+fn parse_located<T: Iterator<Item = char>, O, F>(
+    input: &mut Input<T>,
+    marker: Marker,
+    name: &str,
+    parser: F,
+) -> Result<Located<O>, ParseError>
+where
+    F: Fn(&mut Input<T>) -> Result<O, ParseError>,
+{
+    let value = input.with_context(format!("'{name}' option"), parser)?;
+    Ok(Located {
+        location: input.location_since(&marker),
+        value,
+    })
+}
+
 fn parse_configuration<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Configuration, ParseError> {
     let (.., marker) = input.next_mapping_start()?;
-    let values = parse_until!(input, Event::MappingEnd, next_kv);
+
+    let mut runtime = None;
+    let mut entry_point = None;
+    let mut dependencies = None;
+    let mut extra_dependencies = None;
+    let mut arguments = None;
+    let mut debug = None;
+    let mut meta = None;
+    let mut events = None;
+    let mut requirements = None;
+    let mut process_timeout = None;
+    let mut suspend_timeout = None;
+    let mut exclusive = None;
+    let mut out = None;
+    let mut template = None;
+    let mut parallel_loop_parallelism = None;
+    let mut extra = Vec::new();
+
+    while let Ok(Some((element, key_marker))) = input.peek_string() {
+        input.try_next()?;
+        let name = element.as_str();
+        match name {
+            "runtime" => runtime = Some(parse_located(input, key_marker, name, parse_string)?),
+            "entryPoint" => entry_point = Some(parse_located(input, key_marker, name, parse_string)?),
+            "dependencies" => {
+                dependencies = Some(parse_located(input, key_marker, name, parse_list_of_strings)?)
+            }
+            "extraDependencies" => {
+                extra_dependencies = Some(parse_located(input, key_marker, name, parse_list_of_strings)?)
+            }
+            "arguments" => arguments = Some(parse_located(input, key_marker, name, parse_meta)?),
+            "debug" => debug = Some(parse_located(input, key_marker, name, parse_bool)?),
+            "meta" => meta = Some(parse_located(input, key_marker, name, parse_meta)?),
+            "events" => events = Some(parse_located(input, key_marker, name, parse_meta)?),
+            "requirements" => requirements = Some(parse_located(input, key_marker, name, parse_meta)?),
+            "processTimeout" => process_timeout = Some(parse_located(input, key_marker, name, parse_string)?),
+            "suspendTimeout" => suspend_timeout = Some(parse_located(input, key_marker, name, parse_string)?),
+            "exclusive" => exclusive = Some(input.with_context("'exclusive' block", parse_exclusive)?),
+            "out" => out = Some(parse_located(input, key_marker, name, parse_list_of_strings)?),
+            "template" => template = Some(parse_located(input, key_marker, name, parse_string)?),
+            "parallelLoopParallelism" => {
+                parallel_loop_parallelism = Some(parse_located(input, key_marker, name, parse_i64)?)
+            }
+            _ => {
+                let value = input.with_context(format!("'{element}'"), parse_value)?;
+                extra.push(KV {
+                    location: input.location_since(&key_marker),
+                    key: element,
+                    value,
+                });
+            }
+        }
+    }
     input.next_mapping_end()?;
 
     Ok(Configuration {
-        location: (input.current_document_path(), marker).into(),
-        values,
+        location: input.location_since(&marker),
+        runtime,
+        entry_point,
+        dependencies,
+        extra_dependencies,
+        arguments,
+        debug,
+        meta,
+        events,
+        requirements,
+        process_timeout,
+        suspend_timeout,
+        exclusive,
+        out,
+        template,
+        parallel_loop_parallelism,
+        extra,
     })
 }
 
-fn parse_document<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<ConcordDocument, ParseError> {
-    input.next_document_start()?;
+fn parse_exclusive_mode<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<ExclusiveMode, ParseError> {
+    let (mode, marker) = input.next_string()?;
+    match mode.as_str() {
+        "cancel" => Ok(ExclusiveMode::Cancel),
+        "cancelOld" => Ok(ExclusiveMode::CancelOld),
+        "wait" => Ok(ExclusiveMode::Wait),
+        unknown => Err(ParseError {
+            location: Some(input.location(&marker)),
+            kind: ErrorKind::UnexpectedSyntax,
+            msg: format!(
+                "Unexpected exclusive mode '{unknown}'. Only 'cancel', 'cancelOld' and 'wait' are supported."
+            ),
+        }),
+    }
+}
+
+fn parse_exclusive<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Exclusive, ParseError> {
+    let (_, marker) = input.next_mapping_start()?;
+
+    let location = input.location(&marker);
+    let mut group = None;
+    let mut mode = None;
+
+    while let Ok(Some((element, _))) = input.peek_string() {
+        input.try_next()?;
+        match element.as_str() {
+            "group" => group = Some(input.with_context("exclusive group", parse_string)?),
+            "mode" => mode = Some(input.with_context("exclusive mode", parse_exclusive_mode)?),
+            element => {
+                return Err(ParseError {
+                    location: Some(location),
+                    kind: ErrorKind::UnexpectedSyntax,
+                    msg: format!("Unexpected exclusive element '{element}'"),
+                })
+            }
+        }
+    }
+    input.next_mapping_end()?;
+    let location = input.location_since(&marker);
+
+    let Some(group) = group else {
+        return Err(ParseError {
+            location: Some(location),
+            kind: ErrorKind::UnexpectedSyntax,
+            msg: "The 'group' field is required in the 'exclusive' block".to_owned(),
+        });
+    };
+
+    Ok(Exclusive {
+        location,
+        group,
+        mode,
+    })
+}
+
+/// Options shared by all trigger types.
+#[derive(Default)]
+struct TriggerOptions {
+    entry_point: Option<String>,
+    active_profiles: Option<Vec<String>>,
+    arguments: Option<Vec<KV>>,
+    conditions: Option<Vec<KV>>,
+    exclusive: Option<Exclusive>,
+}
+
+impl TriggerOptions {
+    /// Parses the value of a common trigger option. Returns `false` if `element` is not a common option.
+    fn parse_element<T: Iterator<Item = char>>(
+        &mut self,
+        input: &mut Input<T>,
+        element: &str,
+    ) -> Result<bool, ParseError> {
+        match element {
+            "entryPoint" => self.entry_point = Some(input.with_context("'entryPoint' option", parse_string)?),
+            "activeProfiles" => {
+                self.active_profiles =
+                    Some(input.with_context("'activeProfiles' option", parse_list_of_strings)?)
+            }
+            "arguments" => self.arguments = Some(input.with_context("'arguments' block", parse_meta)?),
+            "conditions" => self.conditions = Some(input.with_context("'conditions' block", parse_meta)?),
+            "exclusive" => self.exclusive = Some(input.with_context("'exclusive' block", parse_exclusive)?),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn require_entry_point(&mut self, location: &Location, trigger_type: &str) -> Result<String, ParseError> {
+        self.entry_point.take().ok_or_else(|| ParseError {
+            location: Some(location.clone()),
+            kind: ErrorKind::UnexpectedSyntax,
+            msg: format!("The 'entryPoint' field is required in the '{trigger_type}' trigger"),
+        })
+    }
+
+    fn unexpected_element(location: &Location, trigger_type: &str, element: &str) -> ParseError {
+        ParseError {
+            location: Some(location.clone()),
+            kind: ErrorKind::UnexpectedSyntax,
+            msg: format!("Unexpected {trigger_type} trigger element '{element}'"),
+        }
+    }
+}
+
+fn parse_github_trigger<T: Iterator<Item = char>>(
+    input: &mut Input<T>,
+) -> Result<TriggerDefinition, ParseError> {
+    let (_, marker) = input.next_mapping_start()?;
+
+    let location = input.location(&marker);
+    let mut options = TriggerOptions::default();
+    let mut version = None;
+    let mut use_initiator = None;
+    let mut use_event_commit_id = None;
+    let mut ignore_empty_push = None;
+
+    while let Ok(Some((element, element_marker))) = input.peek_string() {
+        input.try_next()?;
+        match element.as_str() {
+            "version" => version = Some(input.with_context("'version' option", parse_i64)?),
+            "useInitiator" => use_initiator = Some(input.with_context("'useInitiator' option", parse_bool)?),
+            "useEventCommitId" => {
+                use_event_commit_id = Some(input.with_context("'useEventCommitId' option", parse_bool)?)
+            }
+            "ignoreEmptyPush" => {
+                ignore_empty_push = Some(input.with_context("'ignoreEmptyPush' option", parse_bool)?)
+            }
+            element => {
+                if !options.parse_element(input, element)? {
+                    let location = input.location(&element_marker);
+                    return Err(TriggerOptions::unexpected_element(&location, "github", element));
+                }
+            }
+        }
+    }
+    input.next_mapping_end()?;
+
+    Ok(TriggerDefinition::GitHub {
+        version,
+        use_initiator,
+        use_event_commit_id,
+        ignore_empty_push,
+        entry_point: options.require_entry_point(&location, "github")?,
+        active_profiles: options.active_profiles,
+        arguments: options.arguments,
+        conditions: options.conditions,
+        exclusive: options.exclusive,
+    })
+}
+
+fn parse_cron_trigger<T: Iterator<Item = char>>(
+    input: &mut Input<T>,
+) -> Result<TriggerDefinition, ParseError> {
+    let (_, marker) = input.next_mapping_start()?;
+
+    let location = input.location(&marker);
+    let mut options = TriggerOptions::default();
+    let mut spec = None;
+    let mut timezone = None;
+    let mut run_as = None;
+
+    while let Ok(Some((element, element_marker))) = input.peek_string() {
+        input.try_next()?;
+        match element.as_str() {
+            "spec" => spec = Some(input.with_context("'spec' option", parse_string)?),
+            "timezone" => timezone = Some(input.with_context("'timezone' option", parse_string)?),
+            "runAs" => run_as = Some(input.with_context("'runAs' option", parse_value)?),
+            "conditions" => {
+                let location = input.location(&element_marker);
+                return Err(TriggerOptions::unexpected_element(&location, "cron", &element));
+            }
+            element => {
+                if !options.parse_element(input, element)? {
+                    let location = input.location(&element_marker);
+                    return Err(TriggerOptions::unexpected_element(&location, "cron", element));
+                }
+            }
+        }
+    }
+    input.next_mapping_end()?;
+
+    let Some(spec) = spec else {
+        return Err(ParseError {
+            location: Some(location),
+            kind: ErrorKind::UnexpectedSyntax,
+            msg: "The 'spec' field is required in the 'cron' trigger".to_owned(),
+        });
+    };
+
+    Ok(TriggerDefinition::Cron {
+        spec,
+        timezone,
+        run_as,
+        entry_point: options.require_entry_point(&location, "cron")?,
+        active_profiles: options.active_profiles,
+        arguments: options.arguments,
+        exclusive: options.exclusive,
+    })
+}
+
+fn parse_manual_trigger<T: Iterator<Item = char>>(
+    input: &mut Input<T>,
+) -> Result<TriggerDefinition, ParseError> {
+    let (_, marker) = input.next_mapping_start()?;
+
+    let location = input.location(&marker);
+    let mut options = TriggerOptions::default();
+    let mut name = None;
+
+    while let Ok(Some((element, element_marker))) = input.peek_string() {
+        input.try_next()?;
+        match element.as_str() {
+            "name" => name = Some(input.with_context("'name' option", parse_string)?),
+            "conditions" | "exclusive" => {
+                let location = input.location(&element_marker);
+                return Err(TriggerOptions::unexpected_element(&location, "manual", &element));
+            }
+            element => {
+                if !options.parse_element(input, element)? {
+                    let location = input.location(&element_marker);
+                    return Err(TriggerOptions::unexpected_element(&location, "manual", element));
+                }
+            }
+        }
+    }
+    input.next_mapping_end()?;
+
+    Ok(TriggerDefinition::Manual {
+        name,
+        entry_point: options.require_entry_point(&location, "manual")?,
+        active_profiles: options.active_profiles,
+        arguments: options.arguments,
+    })
+}
+
+fn parse_oneops_trigger<T: Iterator<Item = char>>(
+    input: &mut Input<T>,
+) -> Result<TriggerDefinition, ParseError> {
+    let (_, marker) = input.next_mapping_start()?;
+
+    let location = input.location(&marker);
+    let mut options = TriggerOptions::default();
+    let mut use_initiator = None;
+
+    while let Ok(Some((element, element_marker))) = input.peek_string() {
+        input.try_next()?;
+        match element.as_str() {
+            "useInitiator" => use_initiator = Some(input.with_context("'useInitiator' option", parse_bool)?),
+            element => {
+                if !options.parse_element(input, element)? {
+                    let location = input.location(&element_marker);
+                    return Err(TriggerOptions::unexpected_element(&location, "oneops", element));
+                }
+            }
+        }
+    }
+    input.next_mapping_end()?;
+
+    Ok(TriggerDefinition::OneOps {
+        use_initiator,
+        entry_point: options.require_entry_point(&location, "oneops")?,
+        active_profiles: options.active_profiles,
+        arguments: options.arguments,
+        conditions: options.conditions,
+        exclusive: options.exclusive,
+    })
+}
+
+fn parse_generic_trigger<T: Iterator<Item = char>>(
+    input: &mut Input<T>,
+) -> Result<TriggerDefinition, ParseError> {
+    let (_, marker) = input.next_mapping_start()?;
+
+    let location = input.location(&marker);
+    let mut options = TriggerOptions::default();
+    let mut version = None;
+
+    while let Ok(Some((element, element_marker))) = input.peek_string() {
+        input.try_next()?;
+        match element.as_str() {
+            "version" => version = Some(input.with_context("'version' option", parse_i64)?),
+            element => {
+                if !options.parse_element(input, element)? {
+                    let location = input.location(&element_marker);
+                    return Err(TriggerOptions::unexpected_element(&location, "generic", element));
+                }
+            }
+        }
+    }
+    input.next_mapping_end()?;
+
+    Ok(TriggerDefinition::Generic {
+        version,
+        entry_point: options.require_entry_point(&location, "generic")?,
+        active_profiles: options.active_profiles,
+        arguments: options.arguments,
+        conditions: options.conditions,
+        exclusive: options.exclusive,
+    })
+}
+
+fn parse_unknown_trigger<T: Iterator<Item = char>>(
+    input: &mut Input<T>,
+    event_source: &str,
+) -> Result<TriggerDefinition, ParseError> {
+    input.next_mapping_start()?;
+
+    let mut options = TriggerOptions::default();
+    let mut other_options = Vec::new();
+
+    while let Ok(Some((element, marker))) = input.peek_string() {
+        input.try_next()?;
+        if !options.parse_element(input, &element)? {
+            let value = input.with_context(format!("'{element}'"), parse_value)?;
+            other_options.push(KV {
+                location: input.location_since(&marker),
+                key: element,
+                value,
+            });
+        }
+    }
+    input.next_mapping_end()?;
+
+    Ok(TriggerDefinition::Unknown {
+        event_source: event_source.to_owned(),
+        entry_point: options.entry_point,
+        active_profiles: options.active_profiles,
+        arguments: options.arguments,
+        conditions: options.conditions,
+        exclusive: options.exclusive,
+        options: other_options,
+    })
+}
+
+fn parse_trigger<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Trigger, ParseError> {
+    let (_, marker) = input.next_mapping_start()?;
+
+    let (event_source, _) = input.next_string()?;
+    input.enter_context(format!("'{event_source}' trigger"));
+
+    let trigger = match event_source.as_str() {
+        "github" => parse_github_trigger(input)?,
+        "cron" => parse_cron_trigger(input)?,
+        "manual" => parse_manual_trigger(input)?,
+        "oneops" => parse_oneops_trigger(input)?,
+        "generic" => parse_generic_trigger(input)?,
+        unknown => parse_unknown_trigger(input, unknown)?,
+    };
+
+    input.next_mapping_end()?;
+    input.leave_context();
+
+    Ok(Trigger {
+        location: input.location_since(&marker),
+        trigger,
+    })
+}
+
+fn parse_triggers<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Vec<Trigger>, ParseError> {
+    input.next_sequence_start()?;
+    let result = parse_until!(input, Event::SequenceEnd, parse_trigger, 1);
+    input.next_sequence_end()?;
+    Ok(result)
+}
+
+fn parse_import_secret<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<ImportSecret, ParseError> {
+    let (_, marker) = input.next_mapping_start()?;
+
+    let location = input.location(&marker);
+    let mut org = None;
+    let mut name = None;
+    let mut password = None;
+
+    while let Ok(Some((element, _))) = input.peek_string() {
+        input.try_next()?;
+        match element.as_str() {
+            "org" => org = Some(input.with_context("secret org", parse_string)?),
+            "name" => name = Some(input.with_context("secret name", parse_string)?),
+            "password" => password = Some(input.with_context("secret password", parse_value)?),
+            element => {
+                return Err(ParseError {
+                    location: Some(location),
+                    kind: ErrorKind::UnexpectedSyntax,
+                    msg: format!("Unexpected secret element '{element}'"),
+                })
+            }
+        }
+    }
+    input.next_mapping_end()?;
+    let location = input.location_since(&marker);
+
+    let Some(name) = name else {
+        return Err(ParseError {
+            location: Some(location),
+            kind: ErrorKind::UnexpectedSyntax,
+            msg: "The 'name' field is required in the 'secret' block".to_owned(),
+        });
+    };
+
+    Ok(ImportSecret {
+        location,
+        org,
+        name,
+        password,
+    })
+}
+
+fn parse_git_import<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Import, ParseError> {
+    let (_, marker) = input.next_mapping_start()?;
+
+    let location = input.location(&marker);
+    let mut url = None;
+    let mut name = None;
+    let mut version = None;
+    let mut path = None;
+    let mut dest = None;
+    let mut exclude = None;
+    let mut secret = None;
+
+    while let Ok(Some((element, _))) = input.peek_string() {
+        input.try_next()?;
+        match element.as_str() {
+            "url" => url = Some(input.with_context("'url' option", parse_string)?),
+            "name" => name = Some(input.with_context("'name' option", parse_string)?),
+            "version" => version = Some(input.with_context("'version' option", parse_string)?),
+            "path" => path = Some(input.with_context("'path' option", parse_string)?),
+            "dest" => dest = Some(input.with_context("'dest' option", parse_string)?),
+            "exclude" => exclude = Some(input.with_context("'exclude' option", parse_list_of_strings)?),
+            "secret" => secret = Some(input.with_context("'secret' block", parse_import_secret)?),
+            element => {
+                return Err(ParseError {
+                    location: Some(location),
+                    kind: ErrorKind::UnexpectedSyntax,
+                    msg: format!("Unexpected git import element '{element}'"),
+                })
+            }
+        }
+    }
+    input.next_mapping_end()?;
+    let location = input.location_since(&marker);
+
+    if url.is_none() && name.is_none() {
+        return Err(ParseError {
+            location: Some(location),
+            kind: ErrorKind::UnexpectedSyntax,
+            msg: "Either 'url' or 'name' is required in the 'git' import".to_owned(),
+        });
+    }
+
+    Ok(Import::Git {
+        location,
+        url,
+        name,
+        version,
+        path,
+        dest,
+        exclude,
+        secret,
+    })
+}
+
+fn parse_mvn_import<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Import, ParseError> {
+    let (_, marker) = input.next_mapping_start()?;
+
+    let location = input.location(&marker);
+    let mut url = None;
+    let mut dest = None;
+
+    while let Ok(Some((element, _))) = input.peek_string() {
+        input.try_next()?;
+        match element.as_str() {
+            "url" => url = Some(input.with_context("'url' option", parse_string)?),
+            "dest" => dest = Some(input.with_context("'dest' option", parse_string)?),
+            element => {
+                return Err(ParseError {
+                    location: Some(location),
+                    kind: ErrorKind::UnexpectedSyntax,
+                    msg: format!("Unexpected mvn import element '{element}'"),
+                })
+            }
+        }
+    }
+    input.next_mapping_end()?;
+    let location = input.location_since(&marker);
+
+    let Some(url) = url else {
+        return Err(ParseError {
+            location: Some(location),
+            kind: ErrorKind::UnexpectedSyntax,
+            msg: "The 'url' field is required in the 'mvn' import".to_owned(),
+        });
+    };
+
+    Ok(Import::Mvn { location, url, dest })
+}
+
+fn parse_dir_import<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Import, ParseError> {
+    let (_, marker) = input.next_mapping_start()?;
+
+    let location = input.location(&marker);
+    let mut src = None;
+    let mut dest = None;
+
+    while let Ok(Some((element, _))) = input.peek_string() {
+        input.try_next()?;
+        match element.as_str() {
+            "src" => src = Some(input.with_context("'src' option", parse_string)?),
+            "dest" => dest = Some(input.with_context("'dest' option", parse_string)?),
+            element => {
+                return Err(ParseError {
+                    location: Some(location),
+                    kind: ErrorKind::UnexpectedSyntax,
+                    msg: format!("Unexpected dir import element '{element}'"),
+                })
+            }
+        }
+    }
+    input.next_mapping_end()?;
+    let location = input.location_since(&marker);
+
+    let Some(src) = src else {
+        return Err(ParseError {
+            location: Some(location),
+            kind: ErrorKind::UnexpectedSyntax,
+            msg: "The 'src' field is required in the 'dir' import".to_owned(),
+        });
+    };
+
+    Ok(Import::Dir { location, src, dest })
+}
+
+fn parse_import<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Import, ParseError> {
     input.next_mapping_start()?;
 
+    let (import_type, marker) = input.next_string()?;
+    input.enter_context(format!("'{import_type}' import"));
+
+    let import = match import_type.as_str() {
+        "git" => parse_git_import(input)?,
+        "mvn" => parse_mvn_import(input)?,
+        "dir" => parse_dir_import(input)?,
+        unknown => {
+            return Err(ParseError {
+                location: Some(input.location(&marker)),
+                kind: ErrorKind::UnexpectedSyntax,
+                msg: format!("Unknown import type '{unknown}'. Only 'git', 'mvn' and 'dir' are supported."),
+            })
+        }
+    };
+
+    input.next_mapping_end()?;
+    input.leave_context();
+
+    Ok(import)
+}
+
+fn parse_imports<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Vec<Import>, ParseError> {
+    input.next_sequence_start()?;
+    let result = parse_until!(input, Event::SequenceEnd, parse_import, 1);
+    input.next_sequence_end()?;
+    Ok(result)
+}
+
+fn parse_profile<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Profile, ParseError> {
+    let (name, marker) = input.next_string()?;
+    input.enter_context(format!("'{name}' profile"));
+
+    let location = input.location(&marker);
     let mut configuration = None;
     let mut flows = None;
     let mut forms = None;
-    let mut public_flows = None;
 
-    while let Ok(Some((top_level_element, marker))) = input.peek_string() {
+    input.next_mapping_start()?;
+    while let Ok(Some((element, _))) = input.peek_string() {
         input.try_next()?;
-        match top_level_element.as_str() {
+        match element.as_str() {
             "configuration" => {
                 configuration = Some(input.with_context("configuration", parse_configuration)?)
             }
             "flows" => flows = Some(input.with_context("flows", parse_flows)?),
             "forms" => forms = Some(input.with_context("forms", parse_forms)?),
-            "publicFlows" => public_flows = Some(input.with_context("publicFlows", parse_list_of_strings)?),
             element => {
                 return Err(ParseError {
-                    location: Some((input.current_document_path(), marker).into()),
+                    location: Some(location),
+                    kind: ErrorKind::UnexpectedSyntax,
+                    msg: format!("Unexpected profile element '{element}'"),
+                })
+            }
+        }
+    }
+    input.next_mapping_end()?;
+    let location = input.location_since(&marker);
+
+    input.leave_context();
+
+    Ok(Profile {
+        location,
+        name,
+        configuration,
+        flows,
+        forms,
+    })
+}
+
+fn parse_profiles<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Vec<Profile>, ParseError> {
+    input.next_mapping_start()?;
+    let result = parse_until!(input, Event::MappingEnd, parse_profile, 2);
+    input.next_mapping_end()?;
+    Ok(result)
+}
+
+fn parse_resource_patterns<T: Iterator<Item = char>>(
+    input: &mut Input<T>,
+) -> Result<ResourcePatterns, ParseError> {
+    // short form, a list of includes
+    if let (Event::SequenceStart(..), marker) = input.peek().cloned()? {
+        let includes = parse_list_of_strings(input)?;
+        return Ok(ResourcePatterns {
+            location: input.location_since(&marker),
+            includes,
+            excludes: Vec::new(),
+        });
+    }
+
+    let (_, marker) = input.next_mapping_start()?;
+
+    let location = input.location(&marker);
+    let mut includes = None;
+    let mut excludes = None;
+
+    while let Ok(Some((element, _))) = input.peek_string() {
+        input.try_next()?;
+        match element.as_str() {
+            "includes" => includes = Some(input.with_context("'includes' patterns", parse_list_of_strings)?),
+            "excludes" => excludes = Some(input.with_context("'excludes' patterns", parse_list_of_strings)?),
+            element => {
+                return Err(ParseError {
+                    location: Some(location),
+                    kind: ErrorKind::UnexpectedSyntax,
+                    msg: format!("Unexpected resources element '{element}'"),
+                })
+            }
+        }
+    }
+    input.next_mapping_end()?;
+    let location = input.location_since(&marker);
+
+    Ok(ResourcePatterns {
+        location,
+        includes: includes.unwrap_or_default(),
+        excludes: excludes.unwrap_or_default(),
+    })
+}
+
+fn parse_resources<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Resources, ParseError> {
+    let (_, marker) = input.next_mapping_start()?;
+
+    let location = input.location(&marker);
+    let mut concord = None;
+
+    while let Ok(Some((element, _))) = input.peek_string() {
+        input.try_next()?;
+        match element.as_str() {
+            "concord" => concord = Some(input.with_context("'concord' resources", parse_resource_patterns)?),
+            element => {
+                return Err(ParseError {
+                    location: Some(location),
                     kind: ErrorKind::UnexpectedSyntax,
-                    msg: format!("Unexpected top-level element {element}"),
+                    msg: format!("Unexpected resources element '{element}'"),
                 })
             }
         }
     }
+    input.next_mapping_end()?;
+    let location = input.location_since(&marker);
+
+    Ok(Resources { location, concord })
+}
+
+fn parse_document<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<ConcordDocument, ParseError> {
+    input.next_document_start()?;
+    input.next_mapping_start()?;
+
+    let mut configuration = None;
+    let mut flows = None;
+    let mut forms = None;
+    let mut public_flows = None;
+    let mut triggers = None;
+    let mut imports = None;
+    let mut profiles = None;
+    let mut resources = None;
+
+    while let Ok(Some((top_level_element, marker))) = input.peek_string() {
+        input.try_next()?;
+        input.recoverable(1, |input| {
+            match top_level_element.as_str() {
+                "configuration" => {
+                    configuration = Some(input.with_context("configuration", parse_configuration)?)
+                }
+                "flows" => flows = Some(input.with_context("flows", parse_flows)?),
+                "forms" => forms = Some(input.with_context("forms", parse_forms)?),
+                "publicFlows" => {
+                    public_flows = Some(input.with_context("publicFlows", parse_list_of_located_strings)?)
+                }
+                "triggers" => triggers = Some(input.with_context("triggers", parse_triggers)?),
+                "imports" => imports = Some(input.with_context("imports", parse_imports)?),
+                "profiles" => profiles = Some(input.with_context("profiles", parse_profiles)?),
+                "resources" => resources = Some(input.with_context("resources", parse_resources)?),
+                element => {
+                    return Err(ParseError {
+                        location: Some(input.location(&marker)),
+                        kind: ErrorKind::UnexpectedSyntax,
+                        msg: format!("Unexpected top-level element {element}"),
+                    })
+                }
+            }
+            Ok(())
+        })?;
+    }
 
     input.next_mapping_end()?;
     input.next_document_end()?;
@@ -874,6 +1782,10 @@ fn parse_document<T: Iterator<Item = char>>(input: &mut Input<T>) -> Result<Conc
         flows,
         forms,
         public_flows,
+        triggers,
+        imports,
+        profiles,
+        resources,
     })
 }
 
@@ -887,3 +1799,35 @@ pub fn parse_stream<T: Iterator<Item = char>>(
     input.next_stream_end()?;
     Ok(result)
 }
+
+/// Parses the stream in the error recovery mode: steps, flows, forms and other blocks that fail to parse are
+/// skipped and the parsing continues. Returns all successfully parsed documents and every error found.
+pub fn parse_stream_recovering<T: Iterator<Item = char>>(
+    input: &mut Input<T>,
+) -> (Vec<ConcordDocument>, Vec<ParseError>) {
+    input.set_recovery(true);
+
+    let mut documents = Vec::new();
+    let mut parse = |input: &mut Input<T>| -> Result<(), ParseError> {
+        input.next_stream_start()?;
+        input.enter_context("document");
+        loop {
+            documents.push(parse_document(input)?);
+            if matches!(input.peek()?, (Event::StreamEnd, _)) {
+                break;
+            }
+        }
+        input.leave_context();
+        input.next_stream_end()?;
+        Ok(())
+    };
+    let result = parse(input);
+
+    input.set_recovery(false);
+
+    let mut errors = input.take_errors();
+    if let Err(e) = result {
+        errors.push(e);
+    }
+    (documents, errors)
+}
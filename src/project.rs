@@ -0,0 +1,599 @@
+//! Loading of Concord projects: a root `concord.yml` and the files in the `concord/` directory.
+
+use crate::error::ParseError;
+use crate::input::Input;
+use crate::model::{ConcordDocument, Configuration, Flow, Form, Located, Location, Profile, Value, KV};
+use crate::parser::parse_stream;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+/// The names of the root file, in the order of preference.
+pub const ROOT_FILE_NAMES: [&str; 4] = [".concord.yml", "concord.yml", ".concord.yaml", "concord.yaml"];
+
+/// The files loaded when the root file doesn't have a `resources` block.
+pub const DEFAULT_RESOURCES: &str = "glob:concord/{**/,}{*.,}concord.{yml,yaml}";
+
+#[derive(Debug)]
+pub enum ProjectError {
+    /// a directory that can't be listed
+    Io { path: PathBuf, error: std::io::Error },
+    /// a file that can't be read (with [crate::error::ErrorKind::Io]), decoded or parsed
+    Parse { path: PathBuf, error: ParseError },
+    /// an invalid or unsupported `resources` pattern
+    Pattern { pattern: String, msg: String },
+}
+
+impl Display for ProjectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProjectError::Io { path, error } => write!(f, "{}: {error}", path.display()),
+            ProjectError::Parse { path, error } => write!(f, "{}: {}", path.display(), error.msg),
+            ProjectError::Pattern { pattern, msg } => {
+                write!(f, "Invalid resources pattern '{pattern}': {msg}")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DefinitionKind {
+    Flow,
+    Form,
+}
+
+/// A flow or form defined in more than one place. The last definition wins.
+#[derive(Debug)]
+pub struct Duplicate {
+    pub kind: DefinitionKind,
+    pub name: String,
+    pub path: PathBuf,
+    pub location: Location,
+    pub previous_path: PathBuf,
+    pub previous_location: Location,
+}
+
+#[derive(Debug)]
+pub struct ProjectFile {
+    /// the path relative to the project's root directory, also used as the source id of the locations
+    pub path: PathBuf,
+    /// the number of documents in the file, merged into [Project::document]
+    pub documents: usize,
+}
+
+#[derive(Debug)]
+pub struct Project {
+    pub root: PathBuf,
+    /// the successfully parsed files, in the order of loading: the root file first, then the other files
+    /// sorted by path
+    pub files: Vec<ProjectFile>,
+    /// the documents of all files merged together, see [merge_document]
+    pub document: ConcordDocument,
+    pub duplicates: Vec<Duplicate>,
+    /// the directories that can't be listed and the files that can't be read or parsed are skipped and
+    /// reported here
+    pub errors: Vec<ProjectError>,
+}
+
+impl Project {
+    /// Loads the project from the `root` directory. Only fails if the directory can't be read, the errors
+    /// in the individual files and subdirectories are collected in [Project::errors].
+    pub fn load<P: AsRef<Path>>(root: P) -> std::io::Result<Project> {
+        let root = root.as_ref();
+        std::fs::read_dir(root)?;
+
+        let mut project = Project {
+            root: root.to_owned(),
+            files: Vec::new(),
+            document: ConcordDocument::default(),
+            duplicates: Vec::new(),
+            errors: Vec::new(),
+        };
+
+        let root_file = ROOT_FILE_NAMES
+            .iter()
+            .map(PathBuf::from)
+            .find(|f| root.join(f).is_file());
+        let mut definitions = Definitions::default();
+        if let Some(root_file) = root_file {
+            project.add_file(root, root_file, &mut definitions);
+        }
+
+        // only the root file is loaded so far
+        let resources = project
+            .document
+            .resources
+            .as_ref()
+            .and_then(|resources| resources.concord.as_ref());
+        let (includes, excludes) = match resources {
+            Some(patterns) => (patterns.includes.clone(), patterns.excludes.clone()),
+            None => (vec![DEFAULT_RESOURCES.to_owned()], Vec::new()),
+        };
+        let includes = project.compile_patterns(&includes);
+        let excludes = project.compile_patterns(&excludes);
+
+        // only the directories the included files can be in are listed, not `.git`, `target`, etc.
+        let mut dirs: Vec<&str> = includes
+            .iter()
+            .flat_map(|p| p.dirs.iter().map(String::as_str))
+            .collect();
+        dirs.sort();
+        dirs.dedup();
+        let mut paths = Vec::new();
+        for dir in &dirs {
+            let nested = dirs.iter().any(|parent| is_subdirectory(dir, parent));
+            if dir.is_empty() || (!nested && root.join(dir).is_dir()) {
+                list_files(root, Path::new(dir), &mut paths, &mut project.errors);
+            }
+        }
+        paths.sort();
+        for path in paths {
+            let name = path_str(&path);
+            let is_root_file = ROOT_FILE_NAMES.contains(&name.as_str());
+            if !is_root_file
+                && includes.iter().any(|p| p.matches(&name))
+                && !excludes.iter().any(|p| p.matches(&name))
+            {
+                project.add_file(root, path, &mut definitions);
+            }
+        }
+
+        Ok(project)
+    }
+
+    /// Parses the file and merges its documents into [Project::document].
+    fn add_file(&mut self, root: &Path, path: PathBuf, definitions: &mut Definitions) {
+        let documents = Input::from_path(root.join(&path))
+            .and_then(|input| parse_stream(&mut input.with_source_id(path_str(&path))));
+        match documents {
+            Ok(documents) => {
+                self.files.push(ProjectFile {
+                    path,
+                    documents: documents.len(),
+                });
+                for document in documents {
+                    self.merge(document, definitions);
+                }
+            }
+            Err(error) => self.errors.push(ProjectError::Parse { path, error }),
+        }
+    }
+
+    fn compile_patterns(&mut self, patterns: &[String]) -> Vec<Pattern> {
+        let mut result = Vec::new();
+        for pattern in patterns {
+            match Pattern::new(pattern) {
+                Ok(p) => result.push(p),
+                Err(msg) => self.errors.push(ProjectError::Pattern {
+                    pattern: pattern.clone(),
+                    msg,
+                }),
+            }
+        }
+        result
+    }
+
+    /// Merges the document of the last added file, recording the flows and forms it redefines.
+    fn merge(&mut self, document: ConcordDocument, seen: &mut Definitions) {
+        let idx = self.files.len() - 1;
+        let current = [
+            (
+                DefinitionKind::Flow,
+                &mut seen.flows,
+                definitions(&document.flows, |f: &Flow| (&f.name, &f.location)),
+            ),
+            (
+                DefinitionKind::Form,
+                &mut seen.forms,
+                definitions(&document.forms, |f: &Form| (&f.name, &f.location)),
+            ),
+        ];
+        for (kind, seen, current) in current {
+            for (name, location) in current {
+                if let Some((_, prev_idx, prev_location)) = seen.iter().find(|(n, _, _)| n == name) {
+                    self.duplicates.push(Duplicate {
+                        kind,
+                        name: name.to_owned(),
+                        path: self.files[idx].path.clone(),
+                        location: location.clone(),
+                        previous_path: self.files[*prev_idx].path.clone(),
+                        previous_location: prev_location.clone(),
+                    });
+                }
+                seen.push((name.to_owned(), idx, location.clone()));
+            }
+        }
+        merge_document(&mut self.document, document);
+    }
+}
+
+/// The flows and forms defined by the files loaded so far: the names, the indexes of the files and the
+/// locations.
+#[derive(Default)]
+struct Definitions {
+    flows: Vec<(String, usize, Location)>,
+    forms: Vec<(String, usize, Location)>,
+}
+
+fn definitions<'a, T, F>(items: &'a Option<Vec<T>>, f: F) -> Vec<(&'a str, &'a Location)>
+where
+    F: Fn(&'a T) -> (&'a String, &'a Location),
+{
+    items
+        .iter()
+        .flatten()
+        .map(|item| {
+            let (name, location) = f(item);
+            (name.as_str(), location)
+        })
+        .collect()
+}
+
+/// The path with `/` separators, as matched by the `resources` patterns.
+fn path_str(path: &Path) -> String {
+    let parts: Vec<String> = path.iter().map(|p| p.to_string_lossy().into_owned()).collect();
+    parts.join("/")
+}
+
+/// Whether `dir` is a subdirectory of `parent`, both given as `/`-separated paths relative to the project root.
+fn is_subdirectory(dir: &str, parent: &str) -> bool {
+    dir != parent && (parent.is_empty() || dir.strip_prefix(parent).is_some_and(|rest| rest.starts_with('/')))
+}
+
+/// Lists the files in `dir` recursively, with paths relative to `root`. Symbolic links to directories are not
+/// followed. The directories and entries that can't be read are skipped and reported in `errors`.
+fn list_files(root: &Path, dir: &Path, result: &mut Vec<PathBuf>, errors: &mut Vec<ProjectError>) {
+    let entries = match std::fs::read_dir(root.join(dir)) {
+        Ok(entries) => entries,
+        Err(error) => {
+            errors.push(ProjectError::Io {
+                path: dir.to_owned(),
+                error,
+            });
+            return;
+        }
+    };
+    for entry in entries {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(error) => {
+                errors.push(ProjectError::Io {
+                    path: dir.to_owned(),
+                    error,
+                });
+                continue;
+            }
+        };
+        let path = dir.join(entry.file_name());
+        match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => list_files(root, &path, result, errors),
+            Ok(file_type) if file_type.is_file() || root.join(&path).is_file() => result.push(path),
+            Ok(_) => {}
+            Err(error) => errors.push(ProjectError::Io { path, error }),
+        }
+    }
+}
+
+enum Matcher {
+    /// the alternatives of the `{a,b}` groups, expanded
+    Globs(Vec<Vec<char>>),
+    /// matches the whole path
+    Regex(regex::Regex),
+}
+
+/// A `resources` pattern: `glob:...` or `regex:...`, patterns without a prefix are globs.
+struct Pattern {
+    matcher: Matcher,
+    /// the directories the matching files can be in, `""` for the whole project
+    dirs: Vec<String>,
+}
+
+impl Pattern {
+    fn new(pattern: &str) -> Result<Pattern, String> {
+        if let Some(re) = pattern.strip_prefix("regex:") {
+            let regex = regex::Regex::new(&format!("^(?:{re})$")).map_err(|e| e.to_string())?;
+            return Ok(Pattern {
+                matcher: Matcher::Regex(regex),
+                dirs: vec![regex_dir(re).to_owned()],
+            });
+        }
+        let glob = pattern.strip_prefix("glob:").unwrap_or(pattern);
+        let globs = expand_braces(glob)?;
+        Ok(Pattern {
+            dirs: globs.iter().map(|g| glob_dir(g).to_owned()).collect(),
+            matcher: Matcher::Globs(globs.into_iter().map(|g| g.chars().collect()).collect()),
+        })
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        match &self.matcher {
+            Matcher::Globs(globs) => {
+                let path: Vec<char> = path.chars().collect();
+                globs.iter().any(|glob| glob_matches(glob, &path))
+            }
+            Matcher::Regex(regex) => regex.is_match(path),
+        }
+    }
+}
+
+/// The directory part of the glob's literal prefix: `concord` for `concord/**/*.yml`.
+fn glob_dir(glob: &str) -> &str {
+    let literal = glob
+        .find(['*', '?', '[', '{', '\\'])
+        .map_or(glob, |idx| &glob[..idx]);
+    literal.rfind('/').map_or("", |idx| &glob[..idx])
+}
+
+/// The directory part of the regex's literal prefix: `concord` for `concord/.*\.yml`, or `""` if the regex
+/// has alternatives (`a|b`) at the top level.
+fn regex_dir(regex: &str) -> &str {
+    let regex = regex.strip_prefix('^').unwrap_or(regex);
+    let mut depth = 0;
+    let mut chars = regex.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            '|' if depth == 0 => return "",
+            _ => {}
+        }
+    }
+    let mut literal = match regex.find(|c| "\\.^$*+?()[]{}|".contains(c)) {
+        Some(idx) => &regex[..idx],
+        None => regex,
+    };
+    // the char before a quantifier is optional
+    if regex[literal.len()..].starts_with(['*', '+', '?', '{']) {
+        literal = &literal[..literal.len() - literal.chars().last().map_or(0, char::len_utf8)];
+    }
+    literal.rfind('/').map_or("", |idx| &regex[..idx])
+}
+
+/// Expands `{a,b}` groups (possibly nested) into all alternatives: `x{a,b{c,d}}` -> `xa`, `xbc`, `xbd`.
+fn expand_braces(glob: &str) -> Result<Vec<String>, String> {
+    let Some(open) = glob.find('{') else {
+        return Ok(vec![glob.to_owned()]);
+    };
+    let mut depth = 0;
+    let mut alternatives = Vec::new();
+    let mut start = open + 1;
+    for (idx, c) in glob[open..].char_indices().map(|(i, c)| (open + i, c)) {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    alternatives.push(&glob[start..idx]);
+                    let mut result = Vec::new();
+                    for alternative in alternatives {
+                        let expanded = format!("{}{alternative}{}", &glob[..open], &glob[idx + 1..]);
+                        result.extend(expand_braces(&expanded)?);
+                    }
+                    return Ok(result);
+                }
+            }
+            ',' if depth == 1 => {
+                alternatives.push(&glob[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    Err("unclosed '{'".to_owned())
+}
+
+/// Matches a glob without `{}` groups: `**` matches any chars, `*` and `?` don't match `/`, `[abc]` and
+/// `[a-z]` match one of the chars (`[!abc]` negated).
+fn glob_matches(glob: &[char], path: &[char]) -> bool {
+    match glob.first() {
+        None => path.is_empty(),
+        Some('*') if glob.get(1) == Some(&'*') => {
+            // `**/` also matches no directories at all
+            let rest = &glob[2..];
+            if rest.first() == Some(&'/') && glob_matches(&rest[1..], path) {
+                return true;
+            }
+            (0..=path.len()).any(|i| glob_matches(rest, &path[i..]))
+        }
+        Some('*') => {
+            let rest = &glob[1..];
+            for i in 0..=path.len() {
+                if glob_matches(rest, &path[i..]) {
+                    return true;
+                }
+                if path.get(i) == Some(&'/') {
+                    break;
+                }
+            }
+            false
+        }
+        Some('?') => matches!(path.first(), Some(c) if *c != '/') && glob_matches(&glob[1..], &path[1..]),
+        Some('[') => {
+            let Some(end) = glob.iter().skip(2).position(|c| *c == ']').map(|i| i + 2) else {
+                return path.first() == Some(&'[') && glob_matches(&glob[1..], &path[1..]);
+            };
+            let Some(c) = path.first() else {
+                return false;
+            };
+            let (negated, class) = match glob[1] {
+                '!' | '^' => (true, &glob[2..end]),
+                _ => (false, &glob[1..end]),
+            };
+            let mut matched = false;
+            let mut i = 0;
+            while i < class.len() {
+                if i + 2 < class.len() && class[i + 1] == '-' {
+                    matched |= (class[i]..=class[i + 2]).contains(c);
+                    i += 3;
+                } else {
+                    matched |= class[i] == *c;
+                    i += 1;
+                }
+            }
+            matched != negated && *c != '/' && glob_matches(&glob[end + 1..], &path[1..])
+        }
+        Some('\\') if glob.len() > 1 => {
+            path.first() == Some(&glob[1]) && glob_matches(&glob[2..], &path[1..])
+        }
+        Some(g) => path.first() == Some(g) && glob_matches(&glob[1..], &path[1..]),
+    }
+}
+
+/// Merges the mappings: the values of the same keys are merged recursively if both are mappings, otherwise
+/// the values of `other` replace the values of `target`.
+pub fn deep_merge(target: &mut Vec<KV>, other: Vec<KV>) {
+    for kv in other {
+        match target.iter_mut().find(|t| t.key == kv.key) {
+            Some(existing) => match (&mut existing.value, kv.value) {
+                (Value::Mapping(existing), Value::Mapping(other)) => deep_merge(existing, other),
+                (_, value) => {
+                    existing.location = kv.location;
+                    existing.value = value;
+                }
+            },
+            None => target.push(kv),
+        }
+    }
+}
+
+fn merge_located_kvs(target: &mut Option<Located<Vec<KV>>>, other: Option<Located<Vec<KV>>>) {
+    match (target.as_mut(), other) {
+        (Some(target), Some(other)) => deep_merge(&mut target.value, other.value),
+        (None, other) => *target = other,
+        (_, None) => {}
+    }
+}
+
+fn merge_located_lists(target: &mut Option<Located<Vec<String>>>, other: Option<Located<Vec<String>>>) {
+    match (target.as_mut(), other) {
+        (Some(target), Some(other)) => {
+            for item in other.value {
+                if !target.value.contains(&item) {
+                    target.value.push(item);
+                }
+            }
+        }
+        (None, other) => *target = other,
+        (_, None) => {}
+    }
+}
+
+/// Merges the configurations: `arguments`, `meta`, `events`, `requirements` and the non-standard parameters
+/// are merged with [deep_merge], `dependencies`, `extraDependencies` and `out` are concatenated, the other
+/// parameters of `other` override the ones of `target`.
+pub fn merge_configuration(target: &mut Configuration, other: Configuration) {
+    merge_located_kvs(&mut target.arguments, other.arguments);
+    merge_located_kvs(&mut target.meta, other.meta);
+    merge_located_kvs(&mut target.events, other.events);
+    merge_located_kvs(&mut target.requirements, other.requirements);
+    deep_merge(&mut target.extra, other.extra);
+    merge_located_lists(&mut target.dependencies, other.dependencies);
+    merge_located_lists(&mut target.extra_dependencies, other.extra_dependencies);
+    merge_located_lists(&mut target.out, other.out);
+
+    macro_rules! replace {
+        ($($field:ident),*) => {
+            $(if other.$field.is_some() {
+                target.$field = other.$field;
+            })*
+        };
+    }
+    replace!(
+        runtime,
+        entry_point,
+        debug,
+        process_timeout,
+        suspend_timeout,
+        exclusive,
+        template,
+        parallel_loop_parallelism
+    );
+}
+
+/// Adds the items to `target`, replacing the ones with the same names.
+fn merge_by_name<T, F: Fn(&T) -> &str>(target: &mut Option<Vec<T>>, other: Option<Vec<T>>, name: F) {
+    let Some(other) = other else {
+        return;
+    };
+    let target = target.get_or_insert_with(Vec::new);
+    for item in other {
+        match target.iter_mut().find(|t| name(t) == name(&item)) {
+            Some(existing) => *existing = item,
+            None => target.push(item),
+        }
+    }
+}
+
+fn merge_profile(target: &mut Profile, other: Profile) {
+    match (&mut target.configuration, other.configuration) {
+        (Some(target), Some(other)) => merge_configuration(target, other),
+        (target, other @ Some(_)) => *target = other,
+        (_, None) => {}
+    }
+    merge_by_name(&mut target.flows, other.flows, |f| &f.name);
+    merge_by_name(&mut target.forms, other.forms, |f| &f.name);
+}
+
+/// Merges `other` into `target` the way the Concord server merges the files of a project: configurations are
+/// merged with [merge_configuration], flows, forms and profiles with the same names are replaced (profiles
+/// are merged), `publicFlows` are combined, triggers and imports are concatenated. The `resources` of
+/// `target` are kept if set.
+pub fn merge_document(target: &mut ConcordDocument, other: ConcordDocument) {
+    match (&mut target.configuration, other.configuration) {
+        (Some(target), Some(other)) => merge_configuration(target, other),
+        (target, other @ Some(_)) => *target = other,
+        (_, None) => {}
+    }
+    merge_by_name(&mut target.flows, other.flows, |f| &f.name);
+    merge_by_name(&mut target.forms, other.forms, |f| &f.name);
+    if let Some(public_flows) = other.public_flows {
+        let target = target.public_flows.get_or_insert_with(Vec::new);
+        for flow in public_flows {
+            if !target.iter().any(|f| f.value == flow.value) {
+                target.push(flow);
+            }
+        }
+    }
+    if let Some(triggers) = other.triggers {
+        target.triggers.get_or_insert_with(Vec::new).extend(triggers);
+    }
+    if let Some(imports) = other.imports {
+        target.imports.get_or_insert_with(Vec::new).extend(imports);
+    }
+    if let Some(profiles) = other.profiles {
+        let target = target.profiles.get_or_insert_with(Vec::new);
+        for profile in profiles {
+            match target.iter_mut().find(|p| p.name == profile.name) {
+                Some(existing) => merge_profile(existing, profile),
+                None => target.push(profile),
+            }
+        }
+    }
+    if target.resources.is_none() {
+        target.resources = other.resources;
+    }
+}
+
+/// Returns the effective document for the active profiles, applied in the given order: the configuration of
+/// each profile is merged with [merge_configuration] (arguments are deep-merged, dependencies concatenated),
+/// its flows and forms replace the ones with the same names. The result has no profiles. Names of undefined
+/// profiles are ignored.
+pub fn apply_profiles(document: &ConcordDocument, active_profiles: &[&str]) -> ConcordDocument {
+    let mut result = document.clone();
+    let profiles = result.profiles.take().unwrap_or_default();
+    for name in active_profiles {
+        for profile in profiles.iter().filter(|profile| profile.name == *name) {
+            let profile = profile.clone();
+            let overrides = ConcordDocument {
+                configuration: profile.configuration,
+                flows: profile.flows,
+                forms: profile.forms,
+                ..ConcordDocument::default()
+            };
+            merge_document(&mut result, overrides);
+        }
+    }
+    result
+}
@@ -0,0 +1,315 @@
+use std::collections::{HashMap, VecDeque};
+use yaml_rust2::parser::Parser;
+use yaml_rust2::scanner::{Marker, ScanError, TScalarStyle};
+use yaml_rust2::Event;
+
+/// The maximum number of events produced by expanding aliases and merge keys in a single stream.
+/// Protects against "billion laughs" documents where nested aliases grow exponentially.
+pub const MAX_EXPANDED_EVENTS: usize = 100_000;
+
+/// A resolved event.
+#[derive(Clone)]
+pub struct Item {
+    /// The event and its marker. Copied events are marked at the alias or merge key they were copied
+    /// from, as the original marker points to the anchored node, not where the value appears in the document.
+    pub event: (Event, Marker),
+    /// Whether the event was copied from an anchored node or a merged mapping.
+    pub copied: bool,
+}
+
+#[derive(Clone)]
+pub enum ResolveError {
+    Eof,
+    Scan(ScanError),
+    Syntax(Marker, String),
+}
+
+struct Recording {
+    anchor: usize,
+    depth: usize,
+    items: Vec<Item>,
+}
+
+#[derive(Default)]
+struct Frame {
+    mapping: bool,
+    expect_key: bool,
+    // explicit keys of the mapping, they take precedence over the merged ones
+    keys: Vec<String>,
+    // the marker of the `<<` key whose value is being captured
+    merge: Option<Marker>,
+    capture: Option<Vec<Item>>,
+    merged: Vec<(String, Vec<Item>)>,
+}
+
+/// Sits between the yaml-rust2 parser and [crate::input::Input]: replaces aliases with the events of
+/// their anchored nodes and splices the entries of merge keys (`<<`) into the enclosing mappings, so
+/// the rest of the parser never sees either.
+#[derive(Default)]
+pub struct Resolver {
+    // disabled for the lossless syntax tree, which keeps aliases and merge keys as written
+    disabled: bool,
+    queue: VecDeque<Item>,
+    anchors: HashMap<usize, Vec<Item>>,
+    recordings: Vec<Recording>,
+    frames: Vec<Frame>,
+    expanded: usize,
+    // the stream is out of sync after an error, so it is returned by all subsequent calls
+    error: Option<ResolveError>,
+}
+
+impl Resolver {
+    pub fn disabled() -> Self {
+        Resolver {
+            disabled: true,
+            ..Resolver::default()
+        }
+    }
+
+    pub fn next<T: Iterator<Item = char>>(&mut self, yaml: &mut Parser<T>) -> Result<Item, ResolveError> {
+        self.fill(yaml)?;
+        self.queue.pop_front().ok_or(ResolveError::Eof)
+    }
+
+    pub fn peek<T: Iterator<Item = char>>(&mut self, yaml: &mut Parser<T>) -> Result<&Item, ResolveError> {
+        self.fill(yaml)?;
+        self.queue.front().ok_or(ResolveError::Eof)
+    }
+
+    fn fill<T: Iterator<Item = char>>(&mut self, yaml: &mut Parser<T>) -> Result<(), ResolveError> {
+        // the stream end is never captured, so the loop always terminates
+        while self.queue.is_empty() {
+            if let Some(e) = &self.error {
+                return Err(e.clone());
+            }
+            let result = yaml.next_token().map_err(ResolveError::Scan).and_then(|event| {
+                let item = Item { event, copied: false };
+                if self.disabled {
+                    self.queue.push_back(item);
+                    Ok(())
+                } else {
+                    self.resolve_alias(item)
+                }
+            });
+            if let Err(e) = result {
+                self.error = Some(e.clone());
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    fn resolve_alias(&mut self, item: Item) -> Result<(), ResolveError> {
+        let (event, marker) = &item.event;
+        let Event::Alias(anchor) = event else {
+            self.record(&item);
+            return self.resolve_merge(item);
+        };
+        let Some(items) = self.anchors.get(anchor) else {
+            let msg = if self.recordings.iter().any(|r| r.anchor == *anchor) {
+                "Recursive alias, the anchor refers to itself"
+            } else {
+                "Unknown anchor"
+            };
+            return Err(ResolveError::Syntax(*marker, msg.to_owned()));
+        };
+        let items: Vec<Item> = items
+            .iter()
+            .map(|item| Item {
+                event: (item.event.0.clone(), *marker),
+                copied: true,
+            })
+            .collect();
+        self.expand(*marker, items.len())?;
+        for item in items {
+            self.record(&item);
+            self.resolve_merge(item)?;
+        }
+        Ok(())
+    }
+
+    fn expand(&mut self, marker: Marker, count: usize) -> Result<(), ResolveError> {
+        self.expanded += count;
+        if self.expanded > MAX_EXPANDED_EVENTS {
+            return Err(ResolveError::Syntax(
+                marker,
+                format!("Too many alias expansions (more than {MAX_EXPANDED_EVENTS} events)"),
+            ));
+        }
+        Ok(())
+    }
+
+    fn record(&mut self, item: &Item) {
+        let anchor = match &item.event.0 {
+            Event::Scalar(_, _, anchor, _)
+            | Event::SequenceStart(anchor, _)
+            | Event::MappingStart(anchor, _) => *anchor,
+            _ => 0,
+        };
+        if anchor > 0 {
+            self.recordings.push(Recording {
+                anchor,
+                depth: 0,
+                items: Vec::new(),
+            });
+        }
+        if self.recordings.is_empty() {
+            return;
+        }
+        for recording in &mut self.recordings {
+            recording.items.push(item.clone());
+            match &item.event.0 {
+                Event::SequenceStart(..) | Event::MappingStart(..) => recording.depth += 1,
+                Event::SequenceEnd | Event::MappingEnd => recording.depth -= 1,
+                _ => {}
+            }
+        }
+        // the recordings are nested, so the completed ones are at the end
+        while self.recordings.last().is_some_and(|r| r.depth == 0) {
+            if let Some(recording) = self.recordings.pop() {
+                self.anchors.insert(recording.anchor, recording.items);
+            }
+        }
+    }
+
+    fn resolve_merge(&mut self, item: Item) -> Result<(), ResolveError> {
+        match &item.event.0 {
+            Event::Scalar(value, style, ..) => {
+                if let Some(frame) = self.frames.last_mut() {
+                    if frame.mapping && frame.expect_key {
+                        if value == "<<" && *style == TScalarStyle::Plain {
+                            frame.merge = Some(item.event.1);
+                            frame.capture = Some(Vec::new());
+                            return self.complete_node();
+                        }
+                        frame.keys.push(value.clone());
+                    }
+                }
+                self.emit(item);
+                self.complete_node()
+            }
+            Event::SequenceStart(..) | Event::MappingStart(..) => {
+                let mapping = matches!(item.event.0, Event::MappingStart(..));
+                self.emit(item);
+                self.frames.push(Frame {
+                    mapping,
+                    expect_key: true,
+                    ..Frame::default()
+                });
+                Ok(())
+            }
+            Event::SequenceEnd | Event::MappingEnd => {
+                if let Some(frame) = self.frames.pop() {
+                    for (key, items) in frame.merged {
+                        if frame.keys.contains(&key) {
+                            continue;
+                        }
+                        self.expand(item.event.1, items.len())?;
+                        for merged in items {
+                            self.emit(merged);
+                        }
+                    }
+                }
+                self.emit(item);
+                self.complete_node()
+            }
+            _ => {
+                self.emit(item);
+                Ok(())
+            }
+        }
+    }
+
+    fn emit(&mut self, item: Item) {
+        match self
+            .frames
+            .iter_mut()
+            .rev()
+            .find_map(|frame| frame.capture.as_mut())
+        {
+            Some(capture) => capture.push(item),
+            None => self.queue.push_back(item),
+        }
+    }
+
+    fn complete_node(&mut self) -> Result<(), ResolveError> {
+        let Some(frame) = self.frames.last_mut() else {
+            return Ok(());
+        };
+        if !frame.mapping {
+            return Ok(());
+        }
+        frame.expect_key = !frame.expect_key;
+        if !frame.expect_key {
+            return Ok(());
+        }
+        let (Some(marker), Some(captured)) = (frame.merge.take(), frame.capture.take()) else {
+            return Ok(());
+        };
+        let mappings = match captured.first().map(|item| &item.event.0) {
+            Some(Event::MappingStart(..)) => vec![captured.as_slice()],
+            Some(Event::SequenceStart(..)) => split_nodes(&captured[1..captured.len() - 1]),
+            _ => return Err(invalid_merge(marker)),
+        };
+        for mapping in mappings {
+            let entries = merge_entries(mapping).ok_or_else(|| invalid_merge(marker))?;
+            for (key, mut items) in entries {
+                // the earlier mappings take precedence
+                if frame.merged.iter().any(|(k, _)| *k == key) {
+                    continue;
+                }
+                for item in items.iter_mut().filter(|item| !item.copied) {
+                    item.event.1 = marker;
+                    item.copied = true;
+                }
+                frame.merged.push((key, items));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn invalid_merge(marker: Marker) -> ResolveError {
+    ResolveError::Syntax(
+        marker,
+        "Expected a mapping or a list of mappings as the value of the merge key".to_owned(),
+    )
+}
+
+/// Splits a run of events into complete nodes.
+fn split_nodes(items: &[Item]) -> Vec<&[Item]> {
+    let mut nodes = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, item) in items.iter().enumerate() {
+        match item.event.0 {
+            Event::SequenceStart(..) | Event::MappingStart(..) => depth += 1,
+            Event::SequenceEnd | Event::MappingEnd => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 {
+            nodes.push(&items[start..=i]);
+            start = i + 1;
+        }
+    }
+    nodes
+}
+
+/// Returns the entries of a mapping node as (key, events of the key and the value) pairs.
+fn merge_entries(mapping: &[Item]) -> Option<Vec<(String, Vec<Item>)>> {
+    if !matches!(mapping.first()?.event.0, Event::MappingStart(..)) {
+        return None;
+    }
+    let nodes = split_nodes(&mapping[1..mapping.len() - 1]);
+    let entries = nodes
+        .chunks(2)
+        .filter_map(|entry| match entry {
+            [key, value] => match &key[0].event.0 {
+                Event::Scalar(name, ..) => Some((name.clone(), [*key, *value].concat())),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    Some(entries)
+}
@@ -0,0 +1,210 @@
+use crate::model::Position;
+use std::sync::Arc;
+use yaml_rust2::scanner::TScalarStyle;
+
+/// The input text, read as a whole before parsing.
+/// yaml-rust2 markers only provide char offsets and only mark the beginning of tokens, so the text is kept
+/// around to compute byte offsets and the ends of nodes.
+pub struct Source {
+    text: Arc<str>,
+    // (char index, byte index) pairs recorded after each multibyte char
+    multibyte: Vec<(usize, usize)>,
+    // the number of chars at the start of the text that the scanner doesn't read (the byte order mark)
+    skipped: usize,
+}
+
+impl Source {
+    /// The `text` starts with `prefix`, which the scanner doesn't read. The positions of nodes are still
+    /// computed as offsets in the whole text.
+    pub fn new(text: Arc<str>, prefix: &str) -> Self {
+        let mut multibyte = Vec::new();
+        for (char_index, (byte_index, c)) in text.char_indices().enumerate() {
+            if c.len_utf8() > 1 {
+                multibyte.push((char_index + 1, byte_index + c.len_utf8()));
+            }
+        }
+        Source {
+            text,
+            multibyte,
+            skipped: prefix.chars().count(),
+        }
+    }
+
+    /// The whole text, including the skipped prefix.
+    pub fn text(&self) -> &Arc<str> {
+        &self.text
+    }
+
+    /// Converts the char index of a scanner's marker into the byte offset in the text.
+    pub fn byte_index(&self, char_index: usize) -> usize {
+        let char_index = char_index + self.skipped;
+        let idx = self.multibyte.partition_point(|(c, _)| *c <= char_index);
+        if idx == 0 {
+            char_index
+        } else {
+            let (c, b) = self.multibyte[idx - 1];
+            b + (char_index - c)
+        }
+    }
+
+    pub fn char_at(&self, index: usize) -> Option<char> {
+        self.text.get(index..).and_then(|s| s.chars().next())
+    }
+
+    /// Returns the position of the byte offset `to`, counting lines and columns starting from `from`.
+    pub fn advance(&self, from: Position, to: usize) -> Position {
+        let mut result = from;
+        let to = to.min(self.text.len());
+        if to <= from.index {
+            return result;
+        }
+        for c in self.text[from.index..to].chars() {
+            if c == '\n' {
+                result.line += 1;
+                result.col = 0;
+            } else {
+                result.col += 1;
+            }
+        }
+        result.index = to;
+        result
+    }
+
+    /// Returns the byte offset right after the scalar that starts at `start`.
+    pub fn scalar_end(&self, start: usize, style: TScalarStyle, value: &str) -> usize {
+        let Some(text) = self.text.get(start..) else {
+            return start;
+        };
+        match style {
+            TScalarStyle::Plain => start + plain_scalar_len(text, value),
+            TScalarStyle::SingleQuoted => start + quoted_scalar_len(text, '\'', None),
+            TScalarStyle::DoubleQuoted => start + quoted_scalar_len(text, '"', Some('\\')),
+            TScalarStyle::Literal | TScalarStyle::Folded => start + block_scalar_len(text, value),
+        }
+    }
+
+    /// yaml-rust2 marks block scalars (`|` or `>`) at the beginning of their content, returns the position of
+    /// the indicator instead.
+    pub fn block_scalar_start(&self, content: Position) -> Position {
+        let Some(text) = self.text.get(..content.index) else {
+            return content;
+        };
+        let mut line_end = text.rfind('\n').unwrap_or(0);
+        while line_end > 0 {
+            let line_start = text[..line_end].rfind('\n').map(|idx| idx + 1).unwrap_or(0);
+            let line = &text[line_start..line_end];
+            if !line.trim().is_empty() {
+                return match block_scalar_indicator(line) {
+                    Some(idx) => Position {
+                        index: line_start + idx,
+                        line: content.line - text[line_start..content.index].matches('\n').count(),
+                        col: line[..idx].chars().count(),
+                    },
+                    None => content,
+                };
+            }
+            line_end = line_start.saturating_sub(1);
+        }
+        content
+    }
+
+    /// Returns the byte offset right after the alias (`*name`) that starts at `start`.
+    pub fn alias_end(&self, start: usize) -> usize {
+        let Some(text) = self.text.get(start..) else {
+            return start;
+        };
+        start
+            + text
+                .char_indices()
+                .skip(1)
+                .find(|(_, c)| c.is_whitespace() || matches!(c, ',' | '[' | ']' | '{' | '}'))
+                .map(|(idx, _)| idx)
+                .unwrap_or(text.len())
+    }
+}
+
+/// Plain scalars have no escapes, only line folding, so the value can be matched against the source
+/// char by char, treating any whitespace in the value as an arbitrary amount of whitespace in the source.
+fn plain_scalar_len(text: &str, value: &str) -> usize {
+    let mut source = text.char_indices().peekable();
+    let mut len = 0;
+    for c in value.chars() {
+        if c.is_whitespace() {
+            while source.next_if(|(_, s)| s.is_whitespace()).is_some() {}
+            continue;
+        }
+        match source.next() {
+            Some((idx, s)) if s == c => len = idx + s.len_utf8(),
+            _ => break,
+        }
+    }
+    len
+}
+
+fn quoted_scalar_len(text: &str, quote: char, escape: Option<char>) -> usize {
+    let mut chars = text.char_indices().skip(1).peekable();
+    while let Some((idx, c)) = chars.next() {
+        if Some(c) == escape {
+            chars.next();
+        } else if c == quote {
+            if escape.is_none() && chars.next_if(|(_, c)| *c == quote).is_some() {
+                // '' is an escaped single quote
+                continue;
+            }
+            return idx + c.len_utf8();
+        }
+    }
+    text.len()
+}
+
+/// Finds the block scalar indicator in the header line: `|` or `>` followed only by the chomping and indentation
+/// indicators and, optionally, a comment.
+fn block_scalar_indicator(line: &str) -> Option<usize> {
+    line.char_indices()
+        .filter(|(_, c)| matches!(c, '|' | '>'))
+        .map(|(idx, _)| idx)
+        .find(|idx| {
+            let rest = line[idx + 1..]
+                .trim_start_matches(['+', '-', '0', '1', '2', '3', '4', '5', '6', '7', '8', '9']);
+            let trimmed = rest.trim_start();
+            trimmed.is_empty() || (trimmed.starts_with('#') && trimmed.len() < rest.len())
+        })
+}
+
+/// A block scalar (`|` or `>`) ends with the last non-empty line indented at least as the first one.
+fn block_scalar_len(text: &str, value: &str) -> usize {
+    // the indicator and the optional chomping/indentation indicators
+    let header_len = text
+        .char_indices()
+        .skip(1)
+        .find(|(_, c)| !matches!(c, '+' | '-' | '0'..='9'))
+        .map(|(idx, _)| idx)
+        .unwrap_or(text.len());
+
+    if value.trim().is_empty() {
+        return header_len;
+    }
+
+    let Some(newline) = text.find('\n') else {
+        return text.len();
+    };
+
+    let mut len = header_len;
+    let mut offset = newline + 1;
+    let mut indent = None;
+    for line in text[offset..].split_inclusive('\n') {
+        let trimmed = line.trim_end_matches(['\n', '\r']);
+        let content = trimmed.trim_start_matches(' ');
+        if !content.trim().is_empty() {
+            let line_indent = trimmed.len() - content.len();
+            match indent {
+                None => indent = Some(line_indent),
+                Some(indent) if line_indent < indent => break,
+                _ => {}
+            }
+            len = offset + trimmed.len();
+        }
+        offset += line.len();
+    }
+    len
+}
@@ -0,0 +1,733 @@
+//! Traversal of the model.
+//!
+//! Each `visit_*` method of [Visitor] and [VisitorMut] calls the corresponding `walk_*` function by default,
+//! which visits the children of the node. Implementations override the methods for the nodes they are
+//! interested in and call the `walk_*` function to continue into the children:
+//!
+//! ```
+//! use concord_v2_parser::model::FlowStep;
+//! use concord_v2_parser::visit::{walk_flow_step, Visitor};
+//!
+//! /// Counts the steps, including the nested ones.
+//! struct StepCounter(usize);
+//!
+//! impl<'a> Visitor<'a> for StepCounter {
+//!     fn visit_flow_step(&mut self, step: &'a FlowStep) {
+//!         self.0 += 1;
+//!         walk_flow_step(self, step);
+//!     }
+//! }
+//! ```
+//!
+//! Documents are walked in this order: the configuration's `arguments`, `meta`, `events`, `requirements` and
+//! non-standard parameters, the flows, the forms, the triggers' `arguments` and `conditions`, and the
+//! profiles' configurations, flows and forms. Imports and resources are not walked.
+
+use crate::model::{
+    ConcordDocument, Configuration, Flow, FlowStep, Form, FormField, Loop, Retry, StepDefinition,
+    TriggerDefinition, Value, KV,
+};
+
+pub trait Visitor<'a> {
+    fn visit_document(&mut self, document: &'a ConcordDocument) {
+        walk_document(self, document);
+    }
+
+    fn visit_flow(&mut self, flow: &'a Flow) {
+        walk_flow(self, flow);
+    }
+
+    fn visit_flow_step(&mut self, step: &'a FlowStep) {
+        walk_flow_step(self, step);
+    }
+
+    fn visit_step_definition(&mut self, step: &'a StepDefinition) {
+        walk_step_definition(self, step);
+    }
+
+    fn visit_value(&mut self, value: &'a Value) {
+        walk_value(self, value);
+    }
+
+    fn visit_kv(&mut self, kv: &'a KV) {
+        walk_kv(self, kv);
+    }
+
+    fn visit_form(&mut self, form: &'a Form) {
+        walk_form(self, form);
+    }
+
+    /// Called for the fields of forms and the fields overridden by form calls.
+    fn visit_form_field(&mut self, field: &'a FormField) {
+        walk_form_field(self, field);
+    }
+
+    /// Called for the strings of steps that are, or may contain, expressions: the `if`, `switch` and `expr`
+    /// values and the names of called flows (e.g. `call: ${flowName}`).
+    fn visit_expression(&mut self, _expression: &'a str) {}
+
+    /// Called for the names of tasks, checkpoints, called forms and awaited events, and for the languages (or
+    /// the resources) of scripts.
+    fn visit_name(&mut self, _name: &'a str) {}
+
+    /// Called for the inline code of scripts.
+    fn visit_script_body(&mut self, _body: &'a str) {}
+}
+
+pub trait VisitorMut {
+    fn visit_document_mut(&mut self, document: &mut ConcordDocument) {
+        walk_document_mut(self, document);
+    }
+
+    fn visit_flow_mut(&mut self, flow: &mut Flow) {
+        walk_flow_mut(self, flow);
+    }
+
+    fn visit_flow_step_mut(&mut self, step: &mut FlowStep) {
+        walk_flow_step_mut(self, step);
+    }
+
+    fn visit_step_definition_mut(&mut self, step: &mut StepDefinition) {
+        walk_step_definition_mut(self, step);
+    }
+
+    fn visit_value_mut(&mut self, value: &mut Value) {
+        walk_value_mut(self, value);
+    }
+
+    fn visit_kv_mut(&mut self, kv: &mut KV) {
+        walk_kv_mut(self, kv);
+    }
+
+    fn visit_form_mut(&mut self, form: &mut Form) {
+        walk_form_mut(self, form);
+    }
+
+    /// The mutable version of [Visitor::visit_form_field].
+    fn visit_form_field_mut(&mut self, field: &mut FormField) {
+        walk_form_field_mut(self, field);
+    }
+
+    /// The mutable version of [Visitor::visit_expression].
+    fn visit_expression_mut(&mut self, _expression: &mut String) {}
+
+    /// The mutable version of [Visitor::visit_name].
+    fn visit_name_mut(&mut self, _name: &mut String) {}
+
+    /// The mutable version of [Visitor::visit_script_body].
+    fn visit_script_body_mut(&mut self, _body: &mut String) {}
+}
+
+fn walk_configuration<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, configuration: &'a Configuration) {
+    let kvs = [
+        &configuration.arguments,
+        &configuration.meta,
+        &configuration.events,
+        &configuration.requirements,
+    ];
+    for kv in kvs.into_iter().flatten().flat_map(|kvs| &kvs.value) {
+        visitor.visit_kv(kv);
+    }
+    for kv in &configuration.extra {
+        visitor.visit_kv(kv);
+    }
+}
+
+pub fn walk_document<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, document: &'a ConcordDocument) {
+    if let Some(configuration) = &document.configuration {
+        walk_configuration(visitor, configuration);
+    }
+    for flow in document.flows.iter().flatten() {
+        visitor.visit_flow(flow);
+    }
+    for form in document.forms.iter().flatten() {
+        visitor.visit_form(form);
+    }
+    for trigger in document.triggers.iter().flatten() {
+        for kv in trigger_kvs(&trigger.trigger) {
+            visitor.visit_kv(kv);
+        }
+    }
+    for profile in document.profiles.iter().flatten() {
+        if let Some(configuration) = &profile.configuration {
+            walk_configuration(visitor, configuration);
+        }
+        for flow in profile.flows.iter().flatten() {
+            visitor.visit_flow(flow);
+        }
+        for form in profile.forms.iter().flatten() {
+            visitor.visit_form(form);
+        }
+    }
+}
+
+pub fn walk_flow<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, flow: &'a Flow) {
+    for step in &flow.steps {
+        visitor.visit_flow_step(step);
+    }
+}
+
+pub fn walk_flow_step<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, step: &'a FlowStep) {
+    visitor.visit_step_definition(&step.step);
+}
+
+fn walk_steps<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, steps: &'a [FlowStep]) {
+    for step in steps {
+        visitor.visit_flow_step(step);
+    }
+}
+
+fn walk_loop<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, looping: &'a Loop) {
+    visitor.visit_value(&looping.items);
+    if let Some(parallelism) = &looping.parallelism {
+        visitor.visit_value(parallelism);
+    }
+}
+
+fn walk_retry<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, retry: &'a Retry) {
+    for value in [&retry.times, &retry.delay, &retry.input].into_iter().flatten() {
+        visitor.visit_value(value);
+    }
+}
+
+/// The `in`, `out`, `error`, `loop`, `retry` and `meta` of task, script and flow calls.
+fn walk_call<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    input: &'a Option<Value>,
+    output: &'a Option<Value>,
+    error: &'a Option<Vec<FlowStep>>,
+    looping: &'a Option<Loop>,
+    retry: &'a Option<Retry>,
+    meta: &'a Option<Vec<KV>>,
+) {
+    for value in [input, output].into_iter().flatten() {
+        visitor.visit_value(value);
+    }
+    if let Some(error) = error {
+        walk_steps(visitor, error);
+    }
+    if let Some(looping) = looping {
+        walk_loop(visitor, looping);
+    }
+    if let Some(retry) = retry {
+        walk_retry(visitor, retry);
+    }
+    meta.iter().flatten().for_each(|kv| visitor.visit_kv(kv));
+}
+
+/// Visits the expressions and names (see [Visitor::visit_expression] and [Visitor::visit_name]), the values
+/// (`in`, `out`, loop and retry options, ...), the overridden form fields, the `meta` entries and the nested
+/// steps of the step, in the order they are usually written.
+pub fn walk_step_definition<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, step: &'a StepDefinition) {
+    match step {
+        StepDefinition::TaskCall {
+            task_name,
+            input,
+            output,
+            error,
+            looping,
+            meta,
+            retry,
+            ..
+        } => {
+            visitor.visit_name(task_name);
+            walk_call(visitor, input, output, error, looping, retry, meta);
+        }
+        StepDefinition::FlowCall {
+            flow_name,
+            input,
+            output,
+            error,
+            looping,
+            meta,
+            retry,
+        } => {
+            visitor.visit_expression(flow_name);
+            walk_call(visitor, input, output, error, looping, retry, meta);
+        }
+        StepDefinition::Script {
+            language_or_ref,
+            body,
+            input,
+            output,
+            error,
+            looping,
+            meta,
+            retry,
+        } => {
+            visitor.visit_name(language_or_ref);
+            if let Some(body) = body {
+                visitor.visit_script_body(body);
+            }
+            walk_call(visitor, input, output, error, looping, retry, meta);
+        }
+        StepDefinition::Expression {
+            expr,
+            output,
+            error,
+            meta,
+        } => {
+            visitor.visit_expression(expr);
+            if let Some(output) = output {
+                visitor.visit_value(output);
+            }
+            if let Some(error) = error {
+                walk_steps(visitor, error);
+            }
+            meta.iter().flatten().for_each(|kv| visitor.visit_kv(kv));
+        }
+        StepDefinition::If {
+            expression,
+            then_steps,
+            else_steps,
+            meta,
+        } => {
+            visitor.visit_expression(expression);
+            walk_steps(visitor, then_steps);
+            if let Some(else_steps) = else_steps {
+                walk_steps(visitor, else_steps);
+            }
+            meta.iter().flatten().for_each(|kv| visitor.visit_kv(kv));
+        }
+        StepDefinition::SetVariables { vars, meta } => {
+            vars.iter().for_each(|kv| visitor.visit_kv(kv));
+            meta.iter().flatten().for_each(|kv| visitor.visit_kv(kv));
+        }
+        StepDefinition::ParallelBlock { steps, output, meta } => {
+            walk_steps(visitor, steps);
+            if let Some(output) = output {
+                visitor.visit_value(output);
+            }
+            meta.iter().flatten().for_each(|kv| visitor.visit_kv(kv));
+        }
+        StepDefinition::Block {
+            steps,
+            output,
+            error,
+            looping,
+            meta,
+        } => {
+            walk_steps(visitor, steps);
+            if let Some(output) = output {
+                visitor.visit_value(output);
+            }
+            if let Some(error) = error {
+                walk_steps(visitor, error);
+            }
+            if let Some(looping) = looping {
+                walk_loop(visitor, looping);
+            }
+            meta.iter().flatten().for_each(|kv| visitor.visit_kv(kv));
+        }
+        StepDefinition::Switch {
+            expression,
+            cases,
+            default,
+            meta,
+        } => {
+            visitor.visit_expression(expression);
+            for case in cases {
+                visitor.visit_value(&case.label);
+                walk_steps(visitor, &case.steps);
+            }
+            if let Some(default) = default {
+                walk_steps(visitor, default);
+            }
+            meta.iter().flatten().for_each(|kv| visitor.visit_kv(kv));
+        }
+        StepDefinition::FormCall {
+            form_name,
+            run_as,
+            values,
+            fields,
+            meta,
+            ..
+        } => {
+            visitor.visit_name(form_name);
+            for value in [run_as, values].into_iter().flatten() {
+                visitor.visit_value(value);
+            }
+            fields
+                .iter()
+                .flatten()
+                .for_each(|field| visitor.visit_form_field(field));
+            meta.iter().flatten().for_each(|kv| visitor.visit_kv(kv));
+        }
+        StepDefinition::Checkpoint { name, meta } => {
+            visitor.visit_name(name);
+            meta.iter().flatten().for_each(|kv| visitor.visit_kv(kv));
+        }
+        StepDefinition::Suspend { event, meta } => {
+            visitor.visit_name(event);
+            meta.iter().flatten().for_each(|kv| visitor.visit_kv(kv));
+        }
+        StepDefinition::Return => {}
+    }
+}
+
+pub fn walk_value<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, value: &'a Value) {
+    match value {
+        Value::Array(items) => items.iter().for_each(|item| visitor.visit_value(item)),
+        Value::Mapping(kvs) => kvs.iter().for_each(|kv| visitor.visit_kv(kv)),
+        Value::String(_) | Value::Boolean(_) | Value::Float(_) | Value::Integer(_) => {}
+    }
+}
+
+pub fn walk_kv<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, kv: &'a KV) {
+    visitor.visit_value(&kv.value);
+}
+
+pub fn walk_form<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, form: &'a Form) {
+    for field in &form.fields {
+        visitor.visit_form_field(field);
+    }
+}
+
+/// Visits the `value`, `allow`, `min` and `max` options and the non-standard options of the field.
+pub fn walk_form_field<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, field: &'a FormField) {
+    let spec = &field.spec;
+    for value in [&spec.value, &spec.allow, &spec.min, &spec.max]
+        .into_iter()
+        .flatten()
+    {
+        visitor.visit_value(value);
+    }
+    spec.extra.iter().for_each(|kv| visitor.visit_kv(kv));
+}
+
+/// The `arguments` and `conditions` of the trigger.
+fn trigger_kvs(trigger: &TriggerDefinition) -> impl Iterator<Item = &KV> {
+    let (arguments, conditions) = match trigger {
+        TriggerDefinition::GitHub {
+            arguments,
+            conditions,
+            ..
+        }
+        | TriggerDefinition::OneOps {
+            arguments,
+            conditions,
+            ..
+        }
+        | TriggerDefinition::Generic {
+            arguments,
+            conditions,
+            ..
+        }
+        | TriggerDefinition::Unknown {
+            arguments,
+            conditions,
+            ..
+        } => (arguments, Some(conditions)),
+        TriggerDefinition::Cron { arguments, .. } | TriggerDefinition::Manual { arguments, .. } => {
+            (arguments, None)
+        }
+    };
+    let conditions = conditions.into_iter().flatten().flatten();
+    arguments.iter().flatten().chain(conditions)
+}
+
+fn trigger_kvs_mut(trigger: &mut TriggerDefinition) -> impl Iterator<Item = &mut KV> {
+    let (arguments, conditions) = match trigger {
+        TriggerDefinition::GitHub {
+            arguments,
+            conditions,
+            ..
+        }
+        | TriggerDefinition::OneOps {
+            arguments,
+            conditions,
+            ..
+        }
+        | TriggerDefinition::Generic {
+            arguments,
+            conditions,
+            ..
+        }
+        | TriggerDefinition::Unknown {
+            arguments,
+            conditions,
+            ..
+        } => (arguments, Some(conditions)),
+        TriggerDefinition::Cron { arguments, .. } | TriggerDefinition::Manual { arguments, .. } => {
+            (arguments, None)
+        }
+    };
+    let conditions = conditions.into_iter().flatten().flatten();
+    arguments.iter_mut().flatten().chain(conditions)
+}
+
+fn walk_configuration_mut<V: VisitorMut + ?Sized>(visitor: &mut V, configuration: &mut Configuration) {
+    let kvs = [
+        &mut configuration.arguments,
+        &mut configuration.meta,
+        &mut configuration.events,
+        &mut configuration.requirements,
+    ];
+    for kv in kvs.into_iter().flatten().flat_map(|kvs| &mut kvs.value) {
+        visitor.visit_kv_mut(kv);
+    }
+    for kv in &mut configuration.extra {
+        visitor.visit_kv_mut(kv);
+    }
+}
+
+pub fn walk_document_mut<V: VisitorMut + ?Sized>(visitor: &mut V, document: &mut ConcordDocument) {
+    if let Some(configuration) = &mut document.configuration {
+        walk_configuration_mut(visitor, configuration);
+    }
+    for flow in document.flows.iter_mut().flatten() {
+        visitor.visit_flow_mut(flow);
+    }
+    for form in document.forms.iter_mut().flatten() {
+        visitor.visit_form_mut(form);
+    }
+    for trigger in document.triggers.iter_mut().flatten() {
+        for kv in trigger_kvs_mut(&mut trigger.trigger) {
+            visitor.visit_kv_mut(kv);
+        }
+    }
+    for profile in document.profiles.iter_mut().flatten() {
+        if let Some(configuration) = &mut profile.configuration {
+            walk_configuration_mut(visitor, configuration);
+        }
+        for flow in profile.flows.iter_mut().flatten() {
+            visitor.visit_flow_mut(flow);
+        }
+        for form in profile.forms.iter_mut().flatten() {
+            visitor.visit_form_mut(form);
+        }
+    }
+}
+
+pub fn walk_flow_mut<V: VisitorMut + ?Sized>(visitor: &mut V, flow: &mut Flow) {
+    for step in &mut flow.steps {
+        visitor.visit_flow_step_mut(step);
+    }
+}
+
+pub fn walk_flow_step_mut<V: VisitorMut + ?Sized>(visitor: &mut V, step: &mut FlowStep) {
+    visitor.visit_step_definition_mut(&mut step.step);
+}
+
+fn walk_steps_mut<V: VisitorMut + ?Sized>(visitor: &mut V, steps: &mut [FlowStep]) {
+    for step in steps {
+        visitor.visit_flow_step_mut(step);
+    }
+}
+
+fn walk_loop_mut<V: VisitorMut + ?Sized>(visitor: &mut V, looping: &mut Loop) {
+    visitor.visit_value_mut(&mut looping.items);
+    if let Some(parallelism) = &mut looping.parallelism {
+        visitor.visit_value_mut(parallelism);
+    }
+}
+
+fn walk_retry_mut<V: VisitorMut + ?Sized>(visitor: &mut V, retry: &mut Retry) {
+    for value in [&mut retry.times, &mut retry.delay, &mut retry.input]
+        .into_iter()
+        .flatten()
+    {
+        visitor.visit_value_mut(value);
+    }
+}
+
+fn walk_call_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    input: &mut Option<Value>,
+    output: &mut Option<Value>,
+    error: &mut Option<Vec<FlowStep>>,
+    looping: &mut Option<Loop>,
+    retry: &mut Option<Retry>,
+    meta: &mut Option<Vec<KV>>,
+) {
+    for value in [input, output].into_iter().flatten() {
+        visitor.visit_value_mut(value);
+    }
+    if let Some(error) = error {
+        walk_steps_mut(visitor, error);
+    }
+    if let Some(looping) = looping {
+        walk_loop_mut(visitor, looping);
+    }
+    if let Some(retry) = retry {
+        walk_retry_mut(visitor, retry);
+    }
+    meta.iter_mut().flatten().for_each(|kv| visitor.visit_kv_mut(kv));
+}
+
+/// The mutable version of [walk_step_definition].
+pub fn walk_step_definition_mut<V: VisitorMut + ?Sized>(visitor: &mut V, step: &mut StepDefinition) {
+    match step {
+        StepDefinition::TaskCall {
+            task_name,
+            input,
+            output,
+            error,
+            looping,
+            meta,
+            retry,
+            ..
+        } => {
+            visitor.visit_name_mut(task_name);
+            walk_call_mut(visitor, input, output, error, looping, retry, meta);
+        }
+        StepDefinition::FlowCall {
+            flow_name,
+            input,
+            output,
+            error,
+            looping,
+            meta,
+            retry,
+        } => {
+            visitor.visit_expression_mut(flow_name);
+            walk_call_mut(visitor, input, output, error, looping, retry, meta);
+        }
+        StepDefinition::Script {
+            language_or_ref,
+            body,
+            input,
+            output,
+            error,
+            looping,
+            meta,
+            retry,
+        } => {
+            visitor.visit_name_mut(language_or_ref);
+            if let Some(body) = body {
+                visitor.visit_script_body_mut(body);
+            }
+            walk_call_mut(visitor, input, output, error, looping, retry, meta);
+        }
+        StepDefinition::Expression {
+            expr,
+            output,
+            error,
+            meta,
+        } => {
+            visitor.visit_expression_mut(expr);
+            if let Some(output) = output {
+                visitor.visit_value_mut(output);
+            }
+            if let Some(error) = error {
+                walk_steps_mut(visitor, error);
+            }
+            meta.iter_mut().flatten().for_each(|kv| visitor.visit_kv_mut(kv));
+        }
+        StepDefinition::If {
+            expression,
+            then_steps,
+            else_steps,
+            meta,
+        } => {
+            visitor.visit_expression_mut(expression);
+            walk_steps_mut(visitor, then_steps);
+            if let Some(else_steps) = else_steps {
+                walk_steps_mut(visitor, else_steps);
+            }
+            meta.iter_mut().flatten().for_each(|kv| visitor.visit_kv_mut(kv));
+        }
+        StepDefinition::SetVariables { vars, meta } => {
+            vars.iter_mut().for_each(|kv| visitor.visit_kv_mut(kv));
+            meta.iter_mut().flatten().for_each(|kv| visitor.visit_kv_mut(kv));
+        }
+        StepDefinition::ParallelBlock { steps, output, meta } => {
+            walk_steps_mut(visitor, steps);
+            if let Some(output) = output {
+                visitor.visit_value_mut(output);
+            }
+            meta.iter_mut().flatten().for_each(|kv| visitor.visit_kv_mut(kv));
+        }
+        StepDefinition::Block {
+            steps,
+            output,
+            error,
+            looping,
+            meta,
+        } => {
+            walk_steps_mut(visitor, steps);
+            if let Some(output) = output {
+                visitor.visit_value_mut(output);
+            }
+            if let Some(error) = error {
+                walk_steps_mut(visitor, error);
+            }
+            if let Some(looping) = looping {
+                walk_loop_mut(visitor, looping);
+            }
+            meta.iter_mut().flatten().for_each(|kv| visitor.visit_kv_mut(kv));
+        }
+        StepDefinition::Switch {
+            expression,
+            cases,
+            default,
+            meta,
+        } => {
+            visitor.visit_expression_mut(expression);
+            for case in cases {
+                visitor.visit_value_mut(&mut case.label);
+                walk_steps_mut(visitor, &mut case.steps);
+            }
+            if let Some(default) = default {
+                walk_steps_mut(visitor, default);
+            }
+            meta.iter_mut().flatten().for_each(|kv| visitor.visit_kv_mut(kv));
+        }
+        StepDefinition::FormCall {
+            form_name,
+            run_as,
+            values,
+            fields,
+            meta,
+            ..
+        } => {
+            visitor.visit_name_mut(form_name);
+            for value in [run_as, values].into_iter().flatten() {
+                visitor.visit_value_mut(value);
+            }
+            fields
+                .iter_mut()
+                .flatten()
+                .for_each(|field| visitor.visit_form_field_mut(field));
+            meta.iter_mut().flatten().for_each(|kv| visitor.visit_kv_mut(kv));
+        }
+        StepDefinition::Checkpoint { name, meta } => {
+            visitor.visit_name_mut(name);
+            meta.iter_mut().flatten().for_each(|kv| visitor.visit_kv_mut(kv));
+        }
+        StepDefinition::Suspend { event, meta } => {
+            visitor.visit_name_mut(event);
+            meta.iter_mut().flatten().for_each(|kv| visitor.visit_kv_mut(kv));
+        }
+        StepDefinition::Return => {}
+    }
+}
+
+pub fn walk_value_mut<V: VisitorMut + ?Sized>(visitor: &mut V, value: &mut Value) {
+    match value {
+        Value::Array(items) => items.iter_mut().for_each(|item| visitor.visit_value_mut(item)),
+        Value::Mapping(kvs) => kvs.iter_mut().for_each(|kv| visitor.visit_kv_mut(kv)),
+        Value::String(_) | Value::Boolean(_) | Value::Float(_) | Value::Integer(_) => {}
+    }
+}
+
+pub fn walk_kv_mut<V: VisitorMut + ?Sized>(visitor: &mut V, kv: &mut KV) {
+    visitor.visit_value_mut(&mut kv.value);
+}
+
+pub fn walk_form_mut<V: VisitorMut + ?Sized>(visitor: &mut V, form: &mut Form) {
+    for field in &mut form.fields {
+        visitor.visit_form_field_mut(field);
+    }
+}
+
+pub fn walk_form_field_mut<V: VisitorMut + ?Sized>(visitor: &mut V, field: &mut FormField) {
+    let spec = &mut field.spec;
+    for value in [&mut spec.value, &mut spec.allow, &mut spec.min, &mut spec.max]
+        .into_iter()
+        .flatten()
+    {
+        visitor.visit_value_mut(value);
+    }
+    spec.extra.iter_mut().for_each(|kv| visitor.visit_kv_mut(kv));
+}
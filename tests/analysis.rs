@@ -0,0 +1,217 @@
+use concord_v2_parser::analysis::{
+    closest_match, edit_distance, matches_dynamic_name, undefined_flows, unreachable_flows,
+};
+use concord_v2_parser::call_graph::CallGraph;
+use concord_v2_parser::input::Input;
+use concord_v2_parser::parser::parse_stream;
+
+#[test]
+fn undefined_calls() {
+    let src = "flows:\n  main:\n    - call: deploy\n    - if: ${x}\n      then:\n        - call: deplyo\n    - call: ${dynamic}\n    - try:\n        - log: x\n      error:\n        - call: cleanup\n  deploy:\n    - return\nprofiles:\n  dev:\n    flows:\n      devOnly:\n        - call: mian\n";
+    let documents = parse_stream(&mut Input::try_from(src).unwrap()).unwrap();
+    let undefined = undefined_flows(&documents[0], &["main", "deploy", "devOnly"]);
+    let found: Vec<_> = undefined
+        .iter()
+        .map(|u| {
+            (
+                u.flow_name.as_str(),
+                u.location.start.line,
+                u.suggestion.as_deref(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("deplyo", 6, Some("deploy")),
+            ("cleanup", 11, None),
+            ("mian", 18, Some("main"))
+        ]
+    );
+}
+
+#[test]
+fn suggestions() {
+    assert_eq!(edit_distance("kitten", "sitting"), 3);
+    assert_eq!(edit_distance("", "abc"), 3);
+    assert_eq!(edit_distance("mian", "main"), 1);
+    assert_eq!(closest_match("Main", ["main", "other"]), Some("main"));
+    assert_eq!(closest_match("xyz", ["main", "other"]), None);
+}
+
+#[test]
+fn call_graph() {
+    let src = "publicFlows:\n  - main\nflows:\n  main:\n    - call: a\n    - switch: ${x}\n      one:\n        - call: b\n      default:\n        - call: ${dynamic}\n  a:\n    - parallel:\n        - call: a\n  b:\n    - if: ${y}\n      then:\n        - call: c\n      else:\n        - call: missing\n  c:\n    - try:\n        - call: b\n      error:\n        - call: b\n";
+    let documents = parse_stream(&mut Input::try_from(src).unwrap()).unwrap();
+    let graph = CallGraph::new(&documents);
+
+    assert_eq!(graph.callees("main"), vec!["a", "b"]);
+    assert_eq!(graph.callers("b"), vec!["main", "c"]);
+    assert_eq!(graph.dynamic_calls.len(), 1);
+    assert_eq!(graph.cycles(), vec![vec!["a"], vec!["b", "c"]]);
+
+    assert_eq!(
+        graph.to_dot(),
+        "digraph flows {\n  node [shape=box];\n  \"main\" [style=\"bold\"];\n  \"a\" [color=red];\n  \
+         \"b\" [color=red];\n  \"c\" [color=red];\n  \"missing\" [style=\"dashed\"];\n  \"main\" -> \"a\";\n  \
+         \"main\" -> \"b\";\n  \"a\" -> \"a\" [color=red];\n  \"b\" -> \"c\" [color=red];\n  \
+         \"b\" -> \"missing\";\n  \"c\" -> \"b\" [color=red];\n}\n"
+    );
+    assert_eq!(
+        graph.to_mermaid(),
+        "flowchart LR\n  f0[\"main\"]\n  f1[\"a\"]\n  f2[\"b\"]\n  f3[\"c\"]\n  f4[\"missing\"]\n  f0 --> f1\n  \
+         f0 --> f2\n  f1 --> f1\n  f2 --> f3\n  f2 --> f4\n  f3 --> f2\n  classDef public stroke-width:3px\n  \
+         class f0 public\n  classDef undefined stroke-dasharray:5 5\n  class f4 undefined\n  \
+         classDef recursive stroke:#f00\n  class f1,f2,f3 recursive\n  linkStyle 2,3,5 stroke:#f00\n"
+    );
+}
+
+#[test]
+fn long_call_chains() {
+    // f0 -> f1 -> ... -> f19999 -> f0, deeper than the recursion the thread's stack allows
+    let count = 20_000;
+    let mut src = String::from("flows:\n");
+    for i in 0..count {
+        src.push_str(&format!("  f{i}:\n    - call: f{}\n", (i + 1) % count));
+    }
+    let documents = parse_stream(&mut Input::try_from(src.as_str()).unwrap()).unwrap();
+    let graph = CallGraph::new(&documents);
+
+    assert_eq!(graph.reachable_from(&["f0"]).len(), count);
+    let cycles = graph.cycles();
+    assert_eq!(cycles.len(), 1);
+    assert_eq!(cycles[0].len(), count);
+    assert_eq!(graph.to_mermaid().matches("-->").count(), count);
+}
+
+#[test]
+fn unreachable() {
+    let main = "configuration:\n  entryPoint: start\nflows:\n  start:\n    - call: a\n    - call: deploy-${env}\n  a:\n    - log: a\n  deploy-dev:\n    - call: helper\n  helper:\n    - log: h\n  unused:\n    - call: a\n  onFailure:\n    - log: failed\n";
+    let other = "triggers:\n  - cron:\n      spec: '* * * * *'\n      entryPoint: nightly\nflows:\n  nightly:\n    - log: n\n  default:\n    - log: d\n";
+    let main = parse_stream(&mut Input::try_from(main).unwrap()).unwrap();
+    let other = parse_stream(&mut Input::try_from(other).unwrap()).unwrap();
+
+    let unreachable = unreachable_flows(&[&main[0], &other[0]]);
+    let found: Vec<_> = unreachable
+        .iter()
+        .map(|f| {
+            (
+                f.document,
+                f.name.as_str(),
+                f.dynamic_call.as_ref().map(|l| l.start.line),
+            )
+        })
+        .collect();
+    // `default` isn't an entry point when `entryPoint` is specified
+    assert_eq!(
+        found,
+        vec![
+            (0, "deploy-dev", Some(6)),
+            (0, "helper", Some(6)),
+            (0, "unused", None),
+            (1, "default", None)
+        ]
+    );
+
+    assert!(matches_dynamic_name("${flow}", "anything"));
+    assert!(matches_dynamic_name("deploy-${env}-${region}", "deploy-dev-us"));
+    assert!(!matches_dynamic_name("deploy-${env}", "helper"));
+}
+
+#[test]
+fn undefined_variables() {
+    let src = r#"configuration:
+  arguments:
+    env: dev
+flows:
+  main:
+    - set:
+        greeting: Hello
+        obj.field: 1
+    - log: "${greting}, ${env}"
+    - call: helper
+      in:
+        target: ${obj.field}
+    - task: http
+      in:
+        url: ${item}
+      loop:
+        items: ${items}
+      out: response
+    - if: "${response.ok && (hasVariable('opt') ? opt : true)}"
+      then:
+        - log: ${lastError}
+    - expr: ${crypto.decrypt(x -> x + execution.processInstanceId)}
+  helper:
+    - log: ${target} in ${workDir}
+    - task: x
+      error:
+        - log: ${lastError.message}
+  orphan:
+    - log: ${target}
+"#;
+    let documents = parse_stream(&mut Input::try_from(src).unwrap()).unwrap();
+    let undefined = concord_v2_parser::analysis::undefined_variables(&[(src, &documents[0])]);
+    let found: Vec<_> = undefined
+        .iter()
+        .map(|u| {
+            let text = &src[u.location.start.index..u.location.end.index];
+            (
+                u.flow.as_str(),
+                text,
+                u.location.start.line,
+                u.location.start.col,
+                u.suggestion.as_deref(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("main", "greting", 9, 14, Some("greeting")),
+            ("main", "items", 17, 17, None),
+            ("main", "lastError", 21, 17, None),
+            ("orphan", "target", 29, 13, None),
+        ]
+    );
+}
+
+#[test]
+fn method_targets() {
+    let src = r#"flows:
+  main:
+    - task: myTask
+    - log: "${user.getName()} ${other}"
+    - log: "${myTask.run()} ${datetime.current()} ${user.name}"
+"#;
+    let documents = parse_stream(&mut Input::try_from(src).unwrap()).unwrap();
+    let undefined = concord_v2_parser::analysis::undefined_variables(&[(src, &documents[0])]);
+    let found: Vec<_> = undefined
+        .iter()
+        .map(|u| (u.name.as_str(), u.location.start.line, u.location.start.col))
+        .collect();
+    // only the known tasks are skipped
+    assert_eq!(found, vec![("user", 4, 14), ("other", 4, 32), ("user", 5, 52)]);
+}
+
+#[test]
+fn sibling_variables() {
+    let src = r#"flows:
+  main:
+    - call: setup
+    - call: use
+  setup:
+    - set:
+        x: 1
+  use:
+    - log: ${x} ${y}
+"#;
+    let documents = parse_stream(&mut Input::try_from(src).unwrap()).unwrap();
+    let undefined = concord_v2_parser::analysis::undefined_variables(&[(src, &documents[0])]);
+    let found: Vec<_> = undefined
+        .iter()
+        .map(|u| (u.flow.as_str(), u.name.as_str()))
+        .collect();
+    // `x` is set by a flow called before `use` by the same caller
+    assert_eq!(found, vec![("use", "y")]);
+}
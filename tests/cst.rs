@@ -0,0 +1,58 @@
+use concord_v2_parser::cst::{self, NodeKind, ScalarStyle, TokenKind};
+
+#[test]
+fn lossless() {
+    let sources = [
+        include_str!("data/complex.concord.yaml"),
+        include_str!("data/errors.concord.yaml"),
+        "",
+        "# just a comment\n",
+        "%YAML 1.2\n---\nflows:   # trailing\n  main:\n  - log: 'it''s'   \n\n\n  - set: {a: [1, 2,3 ], b: \"x\\ty\"}\n...\n",
+        "a: &anchor\n  b: !!str 1\nc: *anchor\nd: !tag [x]\n? complex\n: key\n",
+        "text: >-\n  folded\n  text\n\n# tail\nlist:\n- a\n-   b\n- - nested\n  - x\n",
+        "first: 1\n---\nsecond: 2\n--- # third\n- 3\n",
+        "crlf: 1\r\nnext:\r\n  - x\r\n",
+        "\u{feff}bom: true\nempty:\nnull_in_flow: {a: , b}\n",
+        "a: |  # c\n  x\nb: |\nc: &x >+\n  text\n\n\nd: *x\n",
+        "? |\n  block key\n: v\n",
+        "? >-\n  folded\n  key\n: v\n",
+        "- set:\n    ? |\n      k\n    : v\n",
+    ];
+    for src in sources {
+        let stream = cst::parse(src).unwrap();
+        assert_eq!(stream.to_string(), src, "{stream:#?}");
+    }
+
+    // the `?` of an explicit first key belongs to the mapping
+    let stream = cst::parse("? |\n  k\n: v\n").unwrap();
+    let root = stream.documents().next().unwrap().root().unwrap();
+    assert_eq!((root.start.index, root.end.index), (0, 11));
+}
+
+#[test]
+fn structure() {
+    let src = "flows:\n  # the entry point\n  main:\n    - log: \"Hi!\"\n    - call: other # inline\n";
+    let stream = cst::parse(src).unwrap();
+    let root = stream.documents().next().unwrap().root().unwrap();
+    assert!(matches!(root.kind, NodeKind::Mapping { flow: false }));
+
+    let main = root.get("flows").unwrap().get("main").unwrap();
+    let steps: Vec<_> = main.items().collect();
+    assert_eq!(steps.len(), 2);
+    assert_eq!(steps[1].to_string(), "call: other");
+    assert_eq!((steps[1].start.line, steps[1].start.col), (5, 6));
+
+    let log = steps[0].get("log").unwrap();
+    assert!(matches!(
+        &log.kind,
+        NodeKind::Scalar { style: ScalarStyle::DoubleQuoted, value } if value == "Hi!"
+    ));
+    assert_eq!(
+        stream.comments().collect::<Vec<_>>(),
+        vec!["# the entry point", "# inline"]
+    );
+    assert!(stream
+        .tokens()
+        .iter()
+        .any(|t| t.kind == TokenKind::Indicator && t.text == "-"));
+}
@@ -0,0 +1,225 @@
+use concord_v2_parser::el::{
+    locate, parse_expression, parse_template, Expr, ExprKind, TemplatePart, MAX_CHAIN_LENGTH, MAX_DEPTH,
+};
+use concord_v2_parser::input::Input;
+use concord_v2_parser::model::{DocumentPath, Location, Position, StepDefinition};
+use concord_v2_parser::parser::parse_stream;
+
+fn location(index: usize, line: usize, col: usize) -> Location {
+    let start = Position { index, line, col };
+    Location {
+        path: DocumentPath::none(),
+        start,
+        end: start,
+    }
+}
+
+/// Renders the tree as an s-expression.
+fn sexp(expr: &Expr) -> String {
+    let list = |items: &[Expr]| items.iter().map(sexp).collect::<Vec<_>>().join(" ");
+    match &expr.kind {
+        ExprKind::Null => "null".to_owned(),
+        ExprKind::Boolean(b) => b.to_string(),
+        ExprKind::Integer(i) => i.to_string(),
+        ExprKind::Float(f) => f.clone(),
+        ExprKind::String(s) => format!("'{s}'"),
+        ExprKind::Identifier(i) => i.clone(),
+        ExprKind::Property { target, name } => format!("(. {} {name})", sexp(target)),
+        ExprKind::Index { target, index } => format!("([] {} {})", sexp(target), sexp(index)),
+        ExprKind::MethodCall { target, name, args } => format!("(.{name} {} [{}])", sexp(target), list(args)),
+        ExprKind::Call { target, args } => format!("(call {} [{}])", sexp(target), list(args)),
+        ExprKind::Unary { op, operand } => format!("({op:?} {})", sexp(operand)),
+        ExprKind::Binary { op, left, right } => format!("({op:?} {} {})", sexp(left), sexp(right)),
+        ExprKind::Ternary {
+            condition,
+            then,
+            otherwise,
+        } => format!("(? {} {} {})", sexp(condition), sexp(then), sexp(otherwise)),
+        ExprKind::Lambda { params, body } => format!("(-> [{}] {})", params.join(" "), sexp(body)),
+        ExprKind::List(items) => format!("[{}]", list(items)),
+        ExprKind::Set(items) => format!("#{{{}}}", list(items)),
+        ExprKind::Map(entries) => {
+            let entries: Vec<String> = entries
+                .iter()
+                .map(|(k, v)| format!("{} {}", sexp(k), sexp(v)))
+                .collect();
+            format!("{{{}}}", entries.join(", "))
+        }
+    }
+}
+
+#[test]
+fn expressions() {
+    let cases = [
+        ("a.b.c", "(. (. a b) c)"),
+        ("a['b'][0]", "([] ([] a 'b') 0)"),
+        ("ctx.getVariable(\"x\", 1.5e3)", "(.getVariable ctx ['x' 1.5e3])"),
+        (
+            "hasVariable('x') && !empty y",
+            "(And (call hasVariable ['x']) (Not (Empty y)))",
+        ),
+        (
+            "1 + 2 * 3 - 4 div 2",
+            "(Subtract (Add 1 (Multiply 2 3)) (Divide 4 2))",
+        ),
+        (
+            "a < b == c ge d or not e and f",
+            "(Or (Equal (Less a b) (GreaterOrEqual c d)) (And (Not e) f))",
+        ),
+        ("x ? y : z ? 1 : null", "(? x y (? z 1 null))"),
+        ("-(a + b) += 'c'", "(Concat (Negate (Add a b)) 'c')"),
+        (
+            "items.stream().map(x -> x.name).toList()",
+            "(.toList (.map (.stream items []) [(-> [x] (. x name))]) [])",
+        ),
+        ("((a, b) -> a + b)(1, 2)", "(call (-> [a b] (Add a b)) [1 2])"),
+        ("[1, true, {'k': v}, {1, 2}, {}]", "[1 true {'k' v} #{1 2} {}]"),
+        ("'it\\'s'", "'it's'"),
+        ("1e-3 - 2E+1", "(Subtract 1e-3 2E+1)"),
+        ("1.e5 + 1. + .5", "(Add (Add 1.e5 1.) .5)"),
+        ("a[1].b", "(. ([] a 1) b)"),
+        (
+            "x instanceof java.lang.String and y",
+            "(And (InstanceOf x (. (. java lang) String)) y)",
+        ),
+    ];
+    let base = location(0, 1, 0);
+    for (src, expected) in cases {
+        let expr = parse_expression(src, &base).unwrap_or_else(|e| panic!("{src}: {e:?}"));
+        assert_eq!(sexp(&expr), expected, "{src}");
+    }
+}
+
+#[test]
+fn templates() {
+    let src = "flows:\n  main:\n    - if: ${x.y > 1}\n      then:\n        - log: \"Hello, ${name}!\"\n";
+    let documents = parse_stream(&mut Input::try_from(src).unwrap()).unwrap();
+    let steps = &documents[0].flows.as_ref().unwrap()[0].steps;
+
+    let StepDefinition::If { expression, .. } = &steps[0].step else {
+        panic!("not an if step");
+    };
+    let base = locate(src, &steps[0].location, expression);
+    let template = parse_template(expression, &base).unwrap();
+    assert!(template.is_single_expression());
+    let expr = template.expressions().next().unwrap();
+    // `x.y > 1` starts after `${`
+    assert_eq!((expr.location.start.line, expr.location.start.col), (3, 12));
+    assert_eq!(expr.location.end.col, 19);
+    let ExprKind::Binary { left, .. } = &expr.kind else {
+        panic!("not a binary expression");
+    };
+    assert_eq!(&src[left.location.start.index..left.location.end.index], "x.y");
+
+    let template = parse_template("Hello, ${name}! \\${escaped} #{later}", &location(0, 1, 0)).unwrap();
+    let parts: Vec<String> = template
+        .parts
+        .iter()
+        .map(|part| match part {
+            TemplatePart::Text { text, .. } => text.clone(),
+            TemplatePart::Expression { expr, deferred, .. } => format!("{}:{deferred}", sexp(expr)),
+        })
+        .collect();
+    assert_eq!(parts, ["Hello, ", "name:false", "! ${escaped} ", "later:true"]);
+}
+
+#[test]
+fn errors() {
+    let cases = [
+        ("${a +}", "Unexpected end of the expression", 5),
+        ("${a b}", "Unexpected 'b' in the expression", 4),
+        ("text ${a", "Unterminated expression, expected '}'", 5),
+        ("${'abc}", "Unterminated expression, expected '}'", 0),
+        ("${a ; b}", "Unexpected character ';' in the expression", 4),
+        ("${f(a,)}", "Unexpected ')' in the expression", 6),
+        ("${1e-}", "Unexpected 'e' in the expression", 3),
+        ("${1e5e5}", "Unexpected 'e5' in the expression", 5),
+        ("${1.5.5}", "Unexpected '.5' in the expression", 5),
+    ];
+    for (src, msg, col) in cases {
+        let e = parse_template(src, &location(10, 2, 0)).unwrap_err();
+        assert_eq!(e.msg, msg, "{src}");
+        let start = e.location.unwrap().start;
+        assert_eq!((start.line, start.col, start.index), (2, col, 10 + col), "{src}");
+    }
+}
+
+#[test]
+fn nesting() {
+    let base = location(0, 1, 0);
+    let msg = format!("The expression is nested too deeply (more than {MAX_DEPTH} levels)");
+    let deep = [
+        format!("{}x{}", "(".repeat(20_000), ")".repeat(20_000)),
+        format!("{}x", "!".repeat(20_000)),
+        format!("{}x", "a -> ".repeat(20_000)),
+        format!("x{}", " + (1".repeat(20_000)),
+    ];
+    for src in deep {
+        let e = parse_expression(&src, &base).unwrap_err();
+        assert_eq!(e.msg, msg, "{}", &src[..10]);
+        // also checked in templates
+        let e = parse_template(&format!("${{{src}}}"), &base).unwrap_err();
+        assert_eq!(e.msg, msg, "{}", &src[..10]);
+    }
+
+    // flat chains aren't nested, but their total length is limited
+    let msg = format!("The expression is too long (more than {MAX_CHAIN_LENGTH} chained operators)");
+    let long = [
+        format!("x{}", " + 1".repeat(20_000)),
+        format!("x{}", ".y".repeat(20_000)),
+        format!(
+            "x{}",
+            "[0]".repeat(MAX_CHAIN_LENGTH / 2) + &" && y".repeat(MAX_CHAIN_LENGTH / 2 + 1)
+        ),
+    ];
+    for src in long {
+        let e = parse_expression(&src, &base).unwrap_err();
+        assert_eq!(e.msg, msg, "{}", &src[..10]);
+    }
+    let terms: Vec<String> = (0..100).map(|i| format!("a{i}")).collect();
+    let flat = [
+        terms.join(" && "),
+        format!("a{}", ".b".repeat(100)),
+        format!("f(){}", "(x)".repeat(100)),
+    ];
+    for src in flat {
+        assert!(parse_expression(&src, &base).is_ok(), "{}", &src[..10]);
+    }
+
+    let depth = MAX_DEPTH - 1;
+    let shallow = format!("{}x{}", "(".repeat(depth), ")".repeat(depth));
+    assert!(parse_expression(&shallow, &base).is_ok());
+
+    // the class name of `instanceof` isn't a variable
+    let expr = parse_expression("x instanceof java.lang.String", &base).unwrap();
+    let variables: Vec<String> = expr.free_variables().iter().map(|v| sexp(v)).collect();
+    assert_eq!(variables, ["x"]);
+}
+
+#[test]
+fn large_expressions() {
+    // 40k items with line breaks and multi-byte chars, the positions are computed without rescanning the text
+    let items: Vec<String> = (0..40_000)
+        .map(|i| match i % 10 {
+            0 => format!("\n'é{i}'"),
+            _ => i.to_string(),
+        })
+        .collect();
+    let src = format!("[{}]", items.join(", "));
+    let expr = parse_expression(&src, &location(5, 3, 2)).unwrap();
+    let ExprKind::List(items) = &expr.kind else {
+        panic!("not a list");
+    };
+    assert_eq!(items.len(), 40_000);
+
+    // the last line starts with `'é39990'`
+    let offset = src.rfind("'é39990'").unwrap();
+    let first = &items[39_990].location;
+    assert_eq!(
+        (first.start.index, first.start.line, first.start.col),
+        (5 + offset, 3 + 4_000, 0)
+    );
+    // `é` takes two bytes, but one column
+    let next = &items[39_991].location;
+    assert_eq!((next.start.index - first.start.index, next.start.col), (11, 10));
+}
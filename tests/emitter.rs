@@ -0,0 +1,34 @@
+use concord_v2_parser::emitter::emit_stream;
+use concord_v2_parser::input::Input;
+use concord_v2_parser::parser::parse_stream;
+
+fn round_trip(src: &str) -> String {
+    let mut input = Input::try_from(src).unwrap();
+    let documents = parse_stream(&mut input).unwrap();
+    emit_stream(&documents)
+}
+
+#[test]
+fn complex_round_trip() {
+    let emitted = round_trip(include_str!("data/complex.concord.yaml"));
+    assert_eq!(round_trip(&emitted), emitted, "{emitted}");
+}
+
+#[test]
+fn short_forms() {
+    let src = "flows:\n  main:\n  - log: Hello!\n  - logYaml: ${x}\n  - task: log\n    in:\n      msg: \"multi\\nline\"\n    out: result\n  - name: Fail\n    throw: 'oops'\n  - call: other\n    in: {a: 1, b: '2', c: [x, 'true']}\n  - return\n\n  other:\n  - set:\n      \"my key\": null\n";
+    assert_eq!(
+        round_trip(src),
+        "flows:\n  main:\n    - log: \"Hello!\"\n    - logYaml: \"${x}\"\n    - task: log\n      in:\n        msg: |-\n          multi\n          line\n      out: \"result\"\n    - name: \"Fail\"\n      throw: \"oops\"\n    - call: other\n      in:\n        a: 1\n        b: \"2\"\n        c:\n          - \"x\"\n          - \"true\"\n    - return\n\n  other:\n    - set:\n        my key: \"null\"\n"
+    );
+}
+
+#[test]
+fn plain_scalars() {
+    let src = "configuration:\n  arguments:\n    a: null\n    b: ~\n    c: yes\n    d: 0x1F\n    e: 1_000\n    f: 'yes'\n    g: \"0x1F\"\n    h:\n    i: 1.2.3\n";
+    let emitted = round_trip(src);
+    assert!(
+        emitted.contains("a: \"null\"\n    b: \"~\"\n    c: \"yes\"\n    d: \"0x1F\"\n    e: \"1_000\"\n    f: \"yes\"\n    g: \"0x1F\"\n    h: \"\"\n    i: \"1.2.3\"\n"),
+        "{emitted}"
+    );
+}
@@ -0,0 +1,68 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn concord_fmt(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_concord-fmt"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn format() {
+    let src = "flows:\n  main:\n  - name: Foo\n    task: foo\n    meta: {a: b}\n    out: result\n    in:\n      x: ${y}\n  other:\n      - if: ${x}\n        then:\n        - return\n";
+    let formatted = "flows:\n  main:\n    - name: Foo\n      task: foo\n      in:\n        x: \"${y}\"\n      out: result\n      meta: {a: b}\n\n  other:\n    - if: \"${x}\"\n      then:\n        - return\n";
+
+    let output = concord_fmt(&[], src);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), formatted);
+
+    assert_eq!(concord_fmt(&["--check"], src).status.code(), Some(1));
+    assert_eq!(concord_fmt(&["--check"], formatted).status.code(), Some(0));
+}
+
+#[test]
+fn errors() {
+    let output = concord_fmt(&["--check"], "flows:\n  main:\n    - lgo: x\n");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Unknown step 'lgo'"));
+}
+
+#[test]
+fn complex_keys() {
+    let src = "flows:\n  main:\n  - set:\n      ? |\n        k\n      : v\n";
+    let formatted = "flows:\n  main:\n    - set:\n        ? |\n          k\n        : v\n";
+
+    let output = concord_fmt(&[], src);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), formatted);
+}
+
+#[test]
+fn comments() {
+    let src = "# the flows\nflows:\n  main: # entry point\n  - log: x   # keep me\n\n\n  # then\n  - task: nothing\n    meta:\n      a: b # why\n    out: r\n  - log: ~\n  - set:\n      n: null\n      hex: 0x1F\n";
+    let formatted = "# the flows\nflows:\n  main: # entry point\n    - log: x # keep me\n\n    # then\n    - task: nothing\n      out: r\n      meta:\n        a: b # why\n    - log: ~\n    - set:\n        n: null\n        hex: 0x1F\n";
+
+    let output = concord_fmt(&[], src);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), formatted);
+    assert_eq!(concord_fmt(&["--check"], formatted).status.code(), Some(0));
+}
+
+#[test]
+fn switch_cases() {
+    let src = "flows:\n  main:\n  - switch: ${x}\n    meta: {a: b}\n    error:\n    - log: e\n    out:\n    - log: o\n    default:\n    - log: d\n    in:\n    - log: i\n";
+    let formatted = "flows:\n  main:\n    - switch: \"${x}\"\n      error:\n        - log: e\n      out:\n        - log: o\n      default:\n        - log: d\n      in:\n        - log: i\n      meta: {a: b}\n";
+
+    let output = concord_fmt(&[], src);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), formatted);
+    assert_eq!(concord_fmt(&["--check"], formatted).status.code(), Some(0));
+}
@@ -0,0 +1,113 @@
+use concord_v2_parser::diagnostic::Severity;
+use concord_v2_parser::input::Input;
+use concord_v2_parser::lint::{self, LintDocument, LintRule};
+use concord_v2_parser::parser::parse_stream;
+use std::process::Command;
+
+const MAIN: &str = "publicFlows:\n  - main\n  - missing\nflows:\n  main:\n    - log: hi\n";
+const OTHER: &str = "flows:\n  other:\n    - log: x\n  main:\n    - return\n";
+
+#[test]
+fn rules() {
+    let parse = |src| parse_stream(&mut Input::try_from(src).unwrap()).unwrap();
+    let (main, other) = (parse(MAIN), parse(OTHER));
+    let documents = vec![
+        LintDocument {
+            file: "main.yml",
+            source: MAIN,
+            document: &main[0],
+        },
+        LintDocument {
+            file: "other.yml",
+            source: OTHER,
+            document: &other[0],
+        },
+    ];
+    let rules = lint::rules();
+    let rules: Vec<&dyn LintRule> = rules
+        .iter()
+        .filter(|r| r.enabled_by_default())
+        .map(|r| r.as_ref())
+        .collect();
+    let lints = lint::run(&rules, &documents);
+
+    assert_eq!(lints.len(), 2, "{lints:#?}");
+    assert_eq!(lints[0].rule, "undefined-public-flow");
+    assert_eq!(lints[0].severity, Severity::Error);
+    let location = lints[0].location.as_ref().unwrap();
+    assert_eq!(
+        (location.start.line, location.start.col, location.end.col),
+        (3, 4, 11)
+    );
+    assert_eq!(lints[1].rule, "duplicate-flow");
+    assert_eq!(
+        (&*lints[1].file, lints[1].location.as_ref().unwrap().start.line),
+        ("other.yml", 4)
+    );
+    assert_eq!(
+        lints[1].help.as_deref(),
+        Some("the previous definition is at main.yml:5:3")
+    );
+
+    let json = lint::to_json(&lints[1..]);
+    assert_eq!(
+        json,
+        "[\n  {\"rule\": \"duplicate-flow\", \"severity\": \"warning\", \"file\": \"other.yml\", \"line\": 4, \
+         \"column\": 3, \"endLine\": 5, \"endColumn\": 13, \"message\": \"Flow 'main' is already defined\", \
+         \"help\": \"the previous definition is at main.yml:5:3\"}\n]\n"
+    );
+}
+
+#[test]
+fn exit_codes() {
+    let dir = std::env::temp_dir().join(format!("concord-lint-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let main = dir.join("main.yml");
+    let other = dir.join("other.yml");
+    let invalid = dir.join("invalid.yml");
+    std::fs::write(&main, MAIN).unwrap();
+    std::fs::write(&other, OTHER).unwrap();
+    std::fs::write(&invalid, "flows:\n  main:\n    - lgo: x\n").unwrap();
+
+    let lint = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_concord-lint"))
+            .args(args)
+            .output()
+            .unwrap()
+    };
+    let (main, other, invalid) = (
+        main.to_str().unwrap(),
+        other.to_str().unwrap(),
+        invalid.to_str().unwrap(),
+    );
+
+    assert_eq!(lint(&[main, other]).status.code(), Some(1));
+    assert_eq!(
+        lint(&["--disable", "undefined-public-flow", main, other])
+            .status
+            .code(),
+        Some(0)
+    );
+    assert_eq!(
+        lint(&["--disable=undefined-public-flow", "--deny-warnings", main, other])
+            .status
+            .code(),
+        Some(1)
+    );
+    assert_eq!(lint(&["--disable", "no-such-rule", main]).status.code(), Some(2));
+
+    let output = lint(&["--format", "sarif", invalid]);
+    assert_eq!(output.status.code(), Some(1));
+    let sarif = String::from_utf8(output.stdout).unwrap();
+    assert!(sarif.contains("\"ruleId\": \"parse-error\""), "{sarif}");
+    assert!(sarif.contains("\"startLine\": 3"), "{sarif}");
+
+    let output = lint(&[main, other]);
+    let text = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        text.contains("warning[duplicate-flow]: Flow 'main' is already defined"),
+        "{text}"
+    );
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -1,5 +1,11 @@
+use concord_v2_parser::diagnostic::Diagnostic;
+use concord_v2_parser::error::ErrorKind;
 use concord_v2_parser::input::Input;
-use concord_v2_parser::parser::parse_stream;
+use concord_v2_parser::model::{
+    Cardinality, ConcordDocument, DocumentPath, FormFieldType, Import, Location, StepDefinition,
+    TriggerDefinition, Value,
+};
+use concord_v2_parser::parser::{parse_stream, parse_stream_recovering};
 
 #[test]
 fn complex() {
@@ -7,3 +13,472 @@ fn complex() {
     let result = parse_stream(&mut input).unwrap();
     dbg!(result);
 }
+
+#[test]
+fn triggers() {
+    let mut input = Input::try_from(include_str!("data/complex.concord.yaml")).unwrap();
+    let result = parse_stream(&mut input).unwrap();
+    let triggers = result[0].triggers.as_ref().unwrap();
+    assert_eq!(triggers.len(), 6);
+    assert!(matches!(
+        &triggers[0].trigger,
+        TriggerDefinition::GitHub { entry_point, version: Some(2), .. } if entry_point == "flowWithLogs"
+    ));
+    assert!(matches!(&triggers[1].trigger, TriggerDefinition::Cron { spec, .. } if spec == "0 12 * * *"));
+    assert!(matches!(
+        &triggers[5].trigger,
+        TriggerDefinition::Unknown { event_source, options, .. } if event_source == "example" && options.len() == 1
+    ));
+}
+
+#[test]
+fn trigger_errors() {
+    let cases = [
+        (
+            "triggers:\n  - cron:\n      spec: '* * * * *'\n      conditions:\n        x: 1\n",
+            "cron",
+            "conditions",
+        ),
+        (
+            "triggers:\n  - manual:\n      entryPoint: main\n      conditions: {}\n",
+            "manual",
+            "conditions",
+        ),
+        (
+            "triggers:\n  - manual:\n      entryPoint: main\n      exclusive:\n        group: g\n",
+            "manual",
+            "exclusive",
+        ),
+        (
+            "triggers:\n  - github:\n      entryPoint: main\n      bogus: 1\n",
+            "github",
+            "bogus",
+        ),
+        (
+            "triggers:\n  - cron:\n      spec: '* * * * *'\n      bogus: 1\n",
+            "cron",
+            "bogus",
+        ),
+        (
+            "triggers:\n  - manual:\n      entryPoint: main\n      bogus: 1\n",
+            "manual",
+            "bogus",
+        ),
+        (
+            "triggers:\n  - oneops:\n      entryPoint: main\n      bogus: 1\n",
+            "oneops",
+            "bogus",
+        ),
+        (
+            "triggers:\n  - generic:\n      entryPoint: main\n      bogus: 1\n",
+            "generic",
+            "bogus",
+        ),
+    ];
+    for (src, trigger_type, element) in cases {
+        let e = parse_stream(&mut Input::try_from(src).unwrap()).unwrap_err();
+        assert_eq!(
+            e.msg,
+            format!("Unexpected {trigger_type} trigger element '{element}'")
+        );
+        // the error points at the key
+        let start = e.location.unwrap().start;
+        assert_eq!((start.line, start.col), (4, 6), "{src}");
+    }
+}
+
+#[test]
+fn imports() {
+    let mut input = Input::try_from(include_str!("data/complex.concord.yaml")).unwrap();
+    let result = parse_stream(&mut input).unwrap();
+    let imports = result[0].imports.as_ref().unwrap();
+    assert_eq!(imports.len(), 4);
+    assert!(
+        matches!(&imports[0], Import::Git { secret: Some(secret), exclude: Some(exclude), .. }
+        if secret.name == "my-key" && exclude.len() == 1)
+    );
+    assert!(matches!(
+        &imports[1],
+        Import::Git {
+            url: None,
+            name: Some(_),
+            ..
+        }
+    ));
+    assert!(matches!(&imports[2], Import::Mvn { dest: Some(dest), .. } if dest == "lib"));
+    assert!(matches!(&imports[3], Import::Dir { src, dest: None, .. } if src == "/opt/shared/flows"));
+}
+
+#[test]
+fn profiles() {
+    let mut input = Input::try_from(include_str!("data/complex.concord.yaml")).unwrap();
+    let result = parse_stream(&mut input).unwrap();
+    let profiles = result[0].profiles.as_ref().unwrap();
+    assert_eq!(profiles.len(), 2);
+    assert_eq!(profiles[0].name, "prod");
+    assert!(profiles[0].configuration.is_some());
+    assert_eq!(profiles[0].flows.as_ref().unwrap()[0].name, "flowWithLogs");
+    assert!(profiles[0].forms.is_none());
+    assert_eq!(profiles[1].name, "dev");
+    assert_eq!(profiles[1].forms.as_ref().unwrap().len(), 1);
+}
+
+#[test]
+fn resources() {
+    let mut input = Input::try_from(include_str!("data/complex.concord.yaml")).unwrap();
+    let result = parse_stream(&mut input).unwrap();
+    let concord = result[0].resources.as_ref().unwrap().concord.as_ref().unwrap();
+    assert_eq!(concord.includes.len(), 2);
+    assert_eq!(concord.excludes, vec!["glob:concord/disabled/**"]);
+
+    let mut input = Input::try_from("resources:\n  concord:\n    - \"glob:flows/*.yml\"\n").unwrap();
+    let result = parse_stream(&mut input).unwrap();
+    let concord = result[0].resources.as_ref().unwrap().concord.as_ref().unwrap();
+    assert_eq!(concord.includes, vec!["glob:flows/*.yml"]);
+    assert!(concord.excludes.is_empty());
+}
+
+#[test]
+fn configuration() {
+    let mut input = Input::try_from(include_str!("data/complex.concord.yaml")).unwrap();
+    let result = parse_stream(&mut input).unwrap();
+    let cfg = result[0].configuration.as_ref().unwrap();
+    assert_eq!(cfg.runtime.as_ref().unwrap().value, "concord-v2");
+    assert_eq!(cfg.dependencies.as_ref().unwrap().value.len(), 2);
+    assert_eq!(cfg.arguments.as_ref().unwrap().value.len(), 3);
+    assert!(cfg.debug.as_ref().unwrap().value);
+    assert_eq!(cfg.process_timeout.as_ref().unwrap().value, "PT15M");
+    assert_eq!(cfg.requirements.as_ref().unwrap().value[0].key, "agent");
+    assert_eq!(cfg.extra.len(), 1);
+    assert_eq!(cfg.extra[0].key, "customParameter");
+}
+
+#[test]
+fn scalars() {
+    let src = "configuration:\n  arguments:\n    a: null\n    b: ~\n    c:\n    d: yes\n    e: On\n    f: 0x1F\n    g: 1_000\n    h: 'yes'\n    i: \"~\"\n    j: 1.2.3\n    k: true\n    l: 12\n    m: 1.5\n";
+    let result = parse_stream(&mut Input::try_from(src).unwrap()).unwrap();
+    let arguments = &result[0]
+        .configuration
+        .as_ref()
+        .unwrap()
+        .arguments
+        .as_ref()
+        .unwrap()
+        .value;
+    let values: Vec<String> = arguments.iter().map(|kv| format!("{:?}", kv.value)).collect();
+    assert_eq!(
+        values,
+        [
+            "String(\"null\")",
+            "String(\"~\")",
+            "String(\"\")",
+            "String(\"yes\")",
+            "String(\"On\")",
+            "String(\"0x1F\")",
+            "String(\"1_000\")",
+            "String(\"yes\")",
+            "String(\"~\")",
+            "String(\"1.2.3\")",
+            "Boolean(true)",
+            "Integer(12)",
+            "Float(\"1.5\")",
+        ]
+    );
+}
+
+#[test]
+fn form_fields() {
+    let mut input = Input::try_from(include_str!("data/complex.concord.yaml")).unwrap();
+    let result = parse_stream(&mut input).unwrap();
+    let form = &result[0].forms.as_ref().unwrap()[1];
+    assert_eq!(form.name, "myOtherForm");
+
+    let age = &form.fields[0].spec;
+    assert!(matches!(age.field_type, FormFieldType::Int));
+    assert!(matches!(age.cardinality, Cardinality::AtLeastOne));
+    assert_eq!(age.label.as_deref(), Some("Age"));
+    assert!(matches!(age.min, Some(Value::Integer(21))));
+
+    let comment = &form.fields[1].spec;
+    assert!(matches!(comment.cardinality, Cardinality::OneOrNone));
+    assert_eq!(comment.readonly, Some(false));
+    assert_eq!(comment.extra[0].key, "inputType");
+
+    let color = &form.fields[2].spec;
+    assert!(matches!(color.cardinality, Cardinality::Any));
+    assert!(matches!(&color.allow, Some(Value::Array(items)) if items.len() == 2));
+}
+
+#[test]
+fn invalid_form_field_type() {
+    let src = "forms:\n  myForm:\n    - x:\n        type: strin+\n";
+    let mut input = Input::try_from(src).unwrap();
+    let err = parse_stream(&mut input).unwrap_err();
+    let location = err.location.unwrap();
+    assert_eq!((location.start.line, location.start.col), (4, 14));
+    assert!(err.msg.contains("strin+"));
+}
+
+#[test]
+fn recovery() {
+    let mut input = Input::try_from(include_str!("data/errors.concord.yaml")).unwrap();
+    assert!(parse_stream(&mut input).is_err());
+
+    let mut input = Input::try_from(include_str!("data/errors.concord.yaml")).unwrap();
+    let (result, errors) = parse_stream_recovering(&mut input);
+    let lines: Vec<usize> = errors
+        .iter()
+        .map(|e| e.location.as_ref().unwrap().start.line)
+        .collect();
+    assert_eq!(lines, vec![7, 8, 15, 17, 21, 27], "{errors:#?}");
+
+    let document = &result[0];
+    assert!(document.configuration.is_some());
+    let flows = document.flows.as_ref().unwrap();
+    assert_eq!(
+        flows.iter().map(|f| f.name.as_str()).collect::<Vec<_>>(),
+        vec!["main", "other"]
+    );
+    assert_eq!(flows[0].steps.len(), 3);
+    assert!(
+        matches!(&flows[0].steps[1].step, StepDefinition::If { then_steps, .. } if then_steps.len() == 1)
+    );
+    let forms = document.forms.as_ref().unwrap();
+    assert_eq!(forms[0].fields.len(), 1);
+    assert_eq!(forms[0].fields[0].name, "y");
+}
+
+#[test]
+fn diagnostic() {
+    let src = "flows:\n  main:\n    - lgo: \"Hello!\"\n";
+    let mut input = Input::try_from(src).unwrap();
+    let err = parse_stream(&mut input).unwrap_err();
+    let rendered = Diagnostic::new(src, &err)
+        .with_file_name("concord.yml")
+        .with_help("did you mean 'log'?")
+        .to_string();
+    assert_eq!(
+        rendered,
+        "error: Unknown step 'lgo'\n\
+         \x20--> concord.yml:3:7\n\
+         \x20 |\n\
+         3 |     - lgo: \"Hello!\"\n\
+         \x20 |       ^^^\n\
+         \x20 = in: document->flows->'main' flow\n\
+         \x20 = help: did you mean 'log'?\n"
+    );
+
+    let colored = Diagnostic::new(src, &err).with_color(true).to_string();
+    assert!(colored.starts_with("\x1b[1;31merror\x1b[0m"));
+
+    // the byte order mark is neither printed nor counted
+    let src = "\u{feff}flws:\n  main: []\n";
+    let err = parse_stream(&mut Input::try_from(src).unwrap()).unwrap_err();
+    let rendered = Diagnostic::new(src, &err).to_string();
+    assert!(rendered.contains("\n1 | flws:\n  | ^^^^\n"), "{rendered}");
+}
+
+#[test]
+fn source_id() {
+    let src = "flows:\n  main:\n    - log: x\n  other:\n    - lgo: x\n";
+    let mut input = Input::try_from(src)
+        .unwrap()
+        .with_source_id("concord/a.concord.yml");
+    let (documents, errors) = parse_stream_recovering(&mut input);
+    let flow = &documents[0].flows.as_ref().unwrap()[0];
+    assert_eq!(flow.location.source_id(), Some("concord/a.concord.yml"));
+    assert_eq!(flow.steps[0].location.source_id(), Some("concord/a.concord.yml"));
+
+    let error = &errors[0];
+    assert_eq!(
+        error.location.as_ref().unwrap().source_id(),
+        Some("concord/a.concord.yml")
+    );
+    assert!(error.to_string().starts_with("concord/a.concord.yml: "));
+    let rendered = Diagnostic::new(src, error).to_string();
+    assert!(rendered.contains(" --> concord/a.concord.yml:5:7"), "{rendered}");
+
+    let mut input = Input::try_from("flows: [\n").unwrap().with_source_id("b.yml");
+    let error = parse_stream(&mut input).unwrap_err();
+    assert_eq!(error.location.unwrap().source_id(), Some("b.yml"));
+}
+
+#[test]
+fn spans() {
+    let src = "flows:\n  main:\n    - log: \"Привет!\"\n    - name: Block\n      task: foo\n      in:\n        text: |\n          line1\n          line2\n\n        list: [1, 2]\n    - return\n  other:\n  - set: {a: 'b''c'}\n";
+    let mut input = Input::try_from(src).unwrap();
+    let result = parse_stream(&mut input).unwrap();
+    let flows = result[0].flows.as_ref().unwrap();
+    let text = |location: &Location| &src[location.start.index..location.end.index];
+
+    let steps = &flows[0].steps;
+    assert_eq!(text(&steps[0].location), "log: \"Привет!\"");
+    assert_eq!(
+        text(&steps[1].location),
+        "name: Block\n      task: foo\n      in:\n        text: |\n          line1\n          line2\n\n        list: [1, 2]"
+    );
+    assert_eq!(steps[1].location.start.line, 4);
+    assert_eq!((steps[1].location.end.line, steps[1].location.end.col), (11, 20));
+    assert_eq!(text(&steps[2].location), "return");
+    assert_eq!(text(&flows[0].location), &src[9..src.find("\n  other").unwrap()]);
+
+    let StepDefinition::TaskCall {
+        input: Some(Value::Mapping(kvs)),
+        ..
+    } = &steps[1].step
+    else {
+        panic!("expected a task call");
+    };
+    assert_eq!(
+        text(&kvs[0].location),
+        "text: |\n          line1\n          line2"
+    );
+    assert_eq!(text(&kvs[1].location), "list: [1, 2]");
+
+    assert_eq!(text(&flows[1].steps[0].location), "set: {a: 'b''c'}");
+}
+
+#[test]
+fn inputs() {
+    let src = "flows:\n  main:\n    - log: \"héllo\"\n";
+    let flow_name = |documents: Vec<ConcordDocument>| documents[0].flows.as_ref().unwrap()[0].name.clone();
+    // the positions are byte offsets in the input, including the byte order mark
+    let log_start = |documents: Vec<ConcordDocument>| {
+        let steps = &documents[0].flows.as_ref().unwrap()[0].steps;
+        let start = steps[0].location.start;
+        (start.index, start.line, start.col)
+    };
+
+    let expected = (src.find("log").unwrap() + 3, 3, 6);
+
+    let with_bom = [b"\xEF\xBB\xBF".as_slice(), src.as_bytes()].concat();
+    let mut input = Input::try_from(with_bom.as_slice()).unwrap();
+    assert_eq!(flow_name(parse_stream(&mut input).unwrap()), "main");
+    let mut input = Input::try_from(with_bom.as_slice()).unwrap();
+    assert_eq!(log_start(parse_stream(&mut input).unwrap()), expected);
+    let text = format!("\u{feff}{src}");
+    let mut input = Input::try_from(text.as_str()).unwrap();
+    assert_eq!(log_start(parse_stream(&mut input).unwrap()), expected);
+
+    let mut input = Input::from_reader(std::io::Cursor::new(with_bom.clone())).unwrap();
+    assert_eq!(log_start(parse_stream(&mut input).unwrap()), expected);
+    let mut input = Input::try_from(String::from_utf8(with_bom).unwrap()).unwrap();
+    assert_eq!(flow_name(parse_stream(&mut input).unwrap()), "main");
+
+    let invalid = b"flows:\n  m\xC3in: []\n";
+    let Err(error) = Input::try_from(invalid.as_slice()) else {
+        panic!("invalid UTF-8 accepted");
+    };
+    assert!(matches!(error.kind, ErrorKind::InvalidEncoding));
+    let start = error.location.unwrap().start;
+    assert_eq!((start.index, start.line, start.col), (10, 2, 3));
+    let Err(error) = Input::try_from([b"\xEF\xBB\xBF".as_slice(), invalid].concat()) else {
+        panic!("invalid UTF-8 accepted");
+    };
+    let start = error.location.unwrap().start;
+    assert_eq!((start.index, start.line, start.col), (13, 2, 3));
+
+    let Err(error) = Input::try_from(b"\xFF\xFEf\0".to_vec()) else {
+        panic!("UTF-16 accepted");
+    };
+    assert!(error.msg.contains("UTF-16"), "{}", error.msg);
+
+    let path = std::env::temp_dir().join(format!("concord-input-{}.yml", std::process::id()));
+    std::fs::write(&path, src).unwrap();
+    let mut input = Input::from_path(&path).unwrap();
+    let documents = parse_stream(&mut input).unwrap();
+    let location = &documents[0].flows.as_ref().unwrap()[0].location;
+    assert_eq!(location.source_id(), Some(path.to_string_lossy().as_ref()));
+    std::fs::remove_file(&path).unwrap();
+
+    let Err(error) = Input::from_path(&path) else {
+        panic!("missing file accepted");
+    };
+    assert!(matches!(error.kind, ErrorKind::Io));
+}
+
+#[test]
+fn thread_and_conversions() {
+    let mut input = Input::try_from("flows:\n  main:\n    - log: x\n").unwrap();
+    let documents = std::thread::spawn(move || parse_stream(&mut input).unwrap().len())
+        .join()
+        .unwrap();
+    assert_eq!(documents, 1);
+
+    // yaml-rust2's markers can be converted, scan errors are reported at the same position
+    let src = "configuration:\n  arguments:\n    a: [b\nc: d";
+    let e = yaml_rust2::YamlLoader::load_from_str(src).unwrap_err();
+    let location = Location::from((DocumentPath::none(), e.marker()));
+    let error = parse_stream(&mut Input::try_from(src).unwrap()).unwrap_err();
+    assert!(matches!(error.kind, ErrorKind::ScanError));
+    let start = error.location.unwrap().start;
+    assert_eq!(start, location.start);
+    assert_eq!(start.line, 4);
+}
+
+#[test]
+fn anchors() {
+    let src = "configuration:\n  arguments:\n    defaults: &defaults\n      url: https://example.com\n      retries: 3\nflows:\n  main:\n    - task: http\n      in:\n        <<: *defaults\n        retries: 5\n        method: GET\n    - &step\n      task: http\n      in: *defaults\n    - <<: [*step, {name: Ignored, task: other}]\n      name: Second\n";
+    let mut input = Input::try_from(src).unwrap();
+    let documents = parse_stream(&mut input).unwrap();
+    let kv_pairs = |value: &Value| match value {
+        Value::Mapping(kvs) => kvs
+            .iter()
+            .map(|kv| format!("{}={:?}", kv.key, kv.value))
+            .collect::<Vec<_>>(),
+        _ => panic!("expected a mapping"),
+    };
+    let steps = &documents[0].flows.as_ref().unwrap()[0].steps;
+    let task_input = |step: &StepDefinition| match step {
+        StepDefinition::TaskCall {
+            task_name,
+            input: Some(input),
+            ..
+        } => (task_name.clone(), kv_pairs(input)),
+        _ => panic!("expected a task call"),
+    };
+
+    // explicit keys take precedence over the merged ones
+    let (task_name, kvs) = task_input(&steps[0].step);
+    assert_eq!(task_name, "http");
+    assert_eq!(
+        kvs,
+        [
+            "retries=Integer(5)",
+            "method=String(\"GET\")",
+            "url=String(\"https://example.com\")"
+        ]
+    );
+
+    let (_, kvs) = task_input(&steps[1].step);
+    assert_eq!(kvs, ["url=String(\"https://example.com\")", "retries=Integer(3)"]);
+    // the copied value is located at the alias
+    let StepDefinition::TaskCall {
+        input: Some(Value::Mapping(copied)),
+        ..
+    } = &steps[1].step
+    else {
+        panic!("expected a task call");
+    };
+    assert_eq!(
+        &src[copied[0].location.start.index..copied[0].location.end.index],
+        "*defaults"
+    );
+
+    let (task_name, _) = task_input(&steps[2].step);
+    assert_eq!(task_name, "http");
+    assert_eq!(steps[2].step_name.as_deref(), Some("Second"));
+
+    let errors = [
+        ("flows:\n  main:\n    - <<: 1\n      log: x\n", "merge key"),
+        (
+            "configuration:\n  arguments:\n    a: &a [x, x, x, x, x, x, x, x, x, x]\n    b: &b [*a, *a, *a, *a, *a, *a, *a, *a, *a, *a]\n    c: &c [*b, *b, *b, *b, *b, *b, *b, *b, *b, *b]\n    d: &d [*c, *c, *c, *c, *c, *c, *c, *c, *c, *c]\n    e: &e [*d, *d, *d, *d, *d, *d, *d, *d, *d, *d]\n    f: [*e, *e, *e, *e, *e, *e, *e, *e, *e, *e]\n",
+            "Too many alias expansions",
+        ),
+    ];
+    for (src, msg) in errors {
+        let error = parse_stream(&mut Input::try_from(src).unwrap()).unwrap_err();
+        assert!(error.msg.contains(msg), "{}", error.msg);
+        assert!(matches!(error.kind, ErrorKind::ScanError));
+    }
+}
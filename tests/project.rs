@@ -0,0 +1,222 @@
+use concord_v2_parser::error::ErrorKind;
+use concord_v2_parser::input::Input;
+use concord_v2_parser::model::Value;
+use concord_v2_parser::parser::parse_stream;
+use concord_v2_parser::project::{apply_profiles, DefinitionKind, Project, ProjectError};
+use std::path::{Path, PathBuf};
+
+/// Creates a project directory with the files under the system's temporary directory.
+fn create_project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("concord-project-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    for (path, content) in files {
+        let path = root.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+    root
+}
+
+#[test]
+fn load() {
+    let root = create_project(
+        "load",
+        &[
+            (
+                "concord.yml",
+                "configuration:\n  dependencies: [a]\n  arguments:\n    x: {a: 1, b: 2}\npublicFlows: [main]\nflows:\n  main:\n    - call: helper\n",
+            ),
+            (
+                "concord/a.concord.yml",
+                "configuration:\n  dependencies: [b]\n  arguments:\n    x: {b: 3}\npublicFlows: [helper]\nflows:\n  helper:\n    - log: a\n  main:\n    - return\n",
+            ),
+            ("concord/sub/concord.yaml", "forms:\n  myForm:\n    - name: {type: string}\n"),
+            ("concord/notes.yml", "not: a concord file\n"),
+            ("concord/broken.concord.yml", "flows:\n  - oops\n"),
+        ],
+    );
+    let project = Project::load(&root).unwrap();
+
+    let paths: Vec<&Path> = project.files.iter().map(|f| f.path.as_path()).collect();
+    assert_eq!(
+        paths,
+        [
+            Path::new("concord.yml"),
+            Path::new("concord/a.concord.yml"),
+            Path::new("concord/sub/concord.yaml")
+        ]
+    );
+    assert_eq!(project.errors.len(), 1);
+    assert!(
+        matches!(&project.errors[0], ProjectError::Parse { path, .. } if path == Path::new("concord/broken.concord.yml"))
+    );
+
+    assert_eq!(project.duplicates.len(), 1);
+    let duplicate = &project.duplicates[0];
+    assert_eq!(duplicate.kind, DefinitionKind::Flow);
+    assert_eq!(duplicate.name, "main");
+    assert_eq!(duplicate.path, Path::new("concord/a.concord.yml"));
+    assert_eq!(duplicate.previous_path, Path::new("concord.yml"));
+
+    let document = &project.document;
+    let flows: Vec<&str> = document.flows.iter().flatten().map(|f| f.name.as_str()).collect();
+    assert_eq!(flows, ["main", "helper"]);
+    // the last definition wins
+    assert_eq!(document.flows.as_ref().unwrap()[0].location.start.line, 9);
+    let location = &document.flows.as_ref().unwrap()[0].location;
+    assert_eq!(location.source_id(), Some("concord/a.concord.yml"));
+    let public_flows = document.public_flows.as_ref().unwrap();
+    let names: Vec<&str> = public_flows.iter().map(|f| f.value.as_str()).collect();
+    assert_eq!(names, ["main", "helper"]);
+    assert_eq!(
+        public_flows[1].location.source_id(),
+        Some("concord/a.concord.yml")
+    );
+    assert_eq!(document.forms.as_ref().unwrap()[0].name, "myForm");
+
+    let configuration = document.configuration.as_ref().unwrap();
+    assert_eq!(configuration.dependencies.as_ref().unwrap().value, ["a", "b"]);
+    let arguments = &configuration.arguments.as_ref().unwrap().value;
+    let Value::Mapping(x) = &arguments[0].value else {
+        panic!("not a mapping");
+    };
+    let x: Vec<(&str, &Value)> = x.iter().map(|kv| (kv.key.as_str(), &kv.value)).collect();
+    assert!(
+        matches!(x[..], [("a", Value::Integer(1)), ("b", Value::Integer(3))]),
+        "{x:?}"
+    );
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn resources() {
+    let root = create_project(
+        "resources",
+        &[
+            (
+                ".concord.yml",
+                "resources:\n  concord:\n    includes: [\"glob:flows/**/*.y*ml\", 'regex:more/[a-z]+\\.yml', 'regex:(', \"glob:missing/**\"]\n    excludes: [\"glob:flows/[!a]*\"]\n",
+            ),
+            ("concord/default.concord.yml", "flows:\n  default:\n    - return\n"),
+            ("flows/a.yml", "flows:\n  a:\n    - return\n"),
+            ("flows/b.yml", "flows:\n  b:\n    - return\n"),
+            ("flows/deep/c.yaml", "flows:\n  c:\n    - return\n"),
+            ("more/d.yml", "flows:\n  d:\n    - return\n"),
+            ("more/e1.yml", "flows:\n  e:\n    - return\n"),
+        ],
+    );
+    let project = Project::load(&root).unwrap();
+
+    let flows: Vec<&str> = project
+        .document
+        .flows
+        .iter()
+        .flatten()
+        .map(|f| f.name.as_str())
+        .collect();
+    assert_eq!(flows, ["a", "c", "d"]);
+    assert!(matches!(&project.errors[..], [ProjectError::Pattern { pattern, .. }] if pattern == "regex:("));
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn invalid_encoding() {
+    let root = create_project(
+        "encoding",
+        &[
+            (
+                "concord.yml",
+                "\u{feff}flows:\n  main:\n    - log: é\n---\npublicFlows: [main]\n",
+            ),
+            ("concord/a.concord.yml", "flows:\n  a:\n    - return\n"),
+        ],
+    );
+    std::fs::write(
+        root.join("concord/b.concord.yml"),
+        b"flows:\n  b:\n    - log: \xff\n",
+    )
+    .unwrap();
+    let project = Project::load(&root).unwrap();
+
+    let files: Vec<(&Path, usize)> = project
+        .files
+        .iter()
+        .map(|f| (f.path.as_path(), f.documents))
+        .collect();
+    assert_eq!(
+        files,
+        [
+            (Path::new("concord.yml"), 2),
+            (Path::new("concord/a.concord.yml"), 1)
+        ]
+    );
+    // the byte order mark is counted in the positions
+    let main = &project.document.flows.as_ref().unwrap()[0];
+    assert_eq!(main.location.start.index, 12);
+    assert!(matches!(
+        &project.errors[..],
+        [ProjectError::Parse { path, error }]
+            if path == Path::new("concord/b.concord.yml") && matches!(error.kind, ErrorKind::InvalidEncoding)
+    ));
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn profiles() {
+    let src = r#"configuration:
+  entryPoint: main
+  dependencies: [base]
+  arguments:
+    db: {host: localhost, port: 5432}
+flows:
+  main:
+    - call: deploy
+  deploy:
+    - log: dev
+profiles:
+  prod:
+    configuration:
+      dependencies: [prod]
+      arguments:
+        db: {host: db.prod}
+    flows:
+      deploy:
+        - log: prod
+      rollback:
+        - return
+  debug:
+    configuration:
+      debug: true
+"#;
+    let documents = parse_stream(&mut Input::try_from(src).unwrap()).unwrap();
+
+    let document = apply_profiles(&documents[0], &["prod", "missing", "debug"]);
+    assert!(document.profiles.is_none());
+    let flows: Vec<&str> = document.flows.iter().flatten().map(|f| f.name.as_str()).collect();
+    assert_eq!(flows, ["main", "deploy", "rollback"]);
+    assert_eq!(document.flows.as_ref().unwrap()[1].location.start.line, 18);
+
+    let configuration = document.configuration.as_ref().unwrap();
+    assert_eq!(configuration.entry_point.as_ref().unwrap().value, "main");
+    assert_eq!(
+        configuration.dependencies.as_ref().unwrap().value,
+        ["base", "prod"]
+    );
+    assert!(configuration.debug.as_ref().unwrap().value);
+    let Value::Mapping(db) = &configuration.arguments.as_ref().unwrap().value[0].value else {
+        panic!("not a mapping");
+    };
+    let db: Vec<(&str, &Value)> = db.iter().map(|kv| (kv.key.as_str(), &kv.value)).collect();
+    assert!(
+        matches!(db[..], [("host", Value::String(ref h)), ("port", Value::Integer(5432))] if h == "db.prod"),
+        "{db:?}"
+    );
+
+    // without active profiles, only the profiles are removed
+    let document = apply_profiles(&documents[0], &[]);
+    assert_eq!(document.flows.as_ref().unwrap().len(), 2);
+    assert!(document.configuration.unwrap().debug.is_none());
+}
@@ -0,0 +1,53 @@
+#![cfg(feature = "serde")]
+
+use concord_v2_parser::input::Input;
+use concord_v2_parser::parser::parse_stream;
+use serde_json::json;
+
+#[test]
+fn complex() {
+    let mut input = Input::try_from(include_str!("data/complex.concord.yaml")).unwrap();
+    let result = parse_stream(&mut input).unwrap();
+    let value = serde_json::to_value(&result).unwrap();
+
+    let document = &value[0];
+    assert_eq!(document["configuration"]["runtime"]["value"], json!("concord-v2"));
+    assert_eq!(
+        document["configuration"]["arguments"]["value"][2]["value"],
+        json!({"type": "float", "value": "3.141519"})
+    );
+
+    let flows = document["flows"].as_array().unwrap();
+    let step = &flows[0]["steps"][0];
+    assert_eq!(step["step"]["type"], json!("taskCall"));
+    assert_eq!(step["step"]["taskName"], json!("log"));
+    assert_eq!(step["step"]["input"]["value"][0]["key"], json!("msg"));
+    assert_eq!(
+        step["location"]["start"],
+        json!({"index": 459, "line": 27, "col": 6})
+    );
+    assert_eq!(
+        step["location"]["path"],
+        json!(["document", "flows", "'flowWithLogs' flow"])
+    );
+    assert_eq!(step["location"]["sourceId"], json!(null));
+
+    let flow_with_return = flows.iter().find(|f| f["name"] == "flowWithReturn").unwrap();
+    assert_eq!(flow_with_return["steps"][0]["step"], json!({"type": "return"}));
+
+    assert_eq!(document["triggers"][0]["trigger"]["type"], json!("github"));
+    assert_eq!(document["imports"][2]["type"], json!("mvn"));
+}
+
+#[test]
+fn source_id() {
+    let mut input = Input::try_from("flows:\n  main:\n    - log: hi\n")
+        .unwrap()
+        .with_source_id("concord/main.concord.yml");
+    let result = parse_stream(&mut input).unwrap();
+    let value = serde_json::to_value(&result).unwrap();
+
+    let location = &value[0]["flows"][0]["steps"][0]["location"];
+    assert_eq!(location["sourceId"], json!("concord/main.concord.yml"));
+    assert_eq!(location["start"], json!({"index": 21, "line": 3, "col": 6}));
+}
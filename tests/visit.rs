@@ -0,0 +1,158 @@
+use concord_v2_parser::emitter::emit_document;
+use concord_v2_parser::input::Input;
+use concord_v2_parser::model::{ConcordDocument, FlowStep, Value, KV};
+use concord_v2_parser::parser::parse_stream;
+use concord_v2_parser::visit::{walk_flow_step, walk_kv, walk_value, walk_value_mut, Visitor, VisitorMut};
+
+const SRC: &str = r#"configuration:
+  arguments:
+    name: world
+flows:
+  main:
+    - if: ${name != null}
+      then:
+        - expr: ${name.trim()}
+    - if: ${x}
+      then:
+        - log: "Hello, ${name}"
+      else:
+        - try:
+            - call: other
+              in:
+                items: ["${name}", 1]
+          error:
+            - switch: ${y}
+              a:
+                - set:
+                    name: ${name}
+              default:
+                - return
+profiles:
+  dev:
+    flows:
+      other:
+        - log: x
+"#;
+
+fn parse() -> ConcordDocument {
+    parse_stream(&mut Input::try_from(SRC).unwrap())
+        .unwrap()
+        .remove(0)
+}
+
+#[test]
+fn visitor() {
+    #[derive(Default)]
+    struct Collector<'a> {
+        steps: usize,
+        keys: Vec<&'a str>,
+        expressions: Vec<&'a str>,
+        names: Vec<&'a str>,
+    }
+
+    impl<'a> Visitor<'a> for Collector<'a> {
+        fn visit_flow_step(&mut self, step: &'a FlowStep) {
+            self.steps += 1;
+            walk_flow_step(self, step);
+        }
+
+        fn visit_kv(&mut self, kv: &'a KV) {
+            self.keys.push(&kv.key);
+            walk_kv(self, kv);
+        }
+
+        fn visit_expression(&mut self, expression: &'a str) {
+            self.expressions.push(expression);
+        }
+
+        fn visit_name(&mut self, name: &'a str) {
+            self.names.push(name);
+        }
+    }
+
+    let document = parse();
+    let mut collector = Collector::default();
+    collector.visit_document(&document);
+    assert_eq!(collector.steps, 10);
+    assert_eq!(collector.keys, ["name", "msg", "items", "name", "msg"]);
+    assert_eq!(
+        collector.expressions,
+        ["${name != null}", "${name.trim()}", "${x}", "other", "${y}"]
+    );
+    assert_eq!(collector.names, ["log", "log"]);
+}
+
+#[test]
+fn forms_and_scripts() {
+    let src = r#"flows:
+  main:
+    - script: js
+      body: println('${x}')
+    - suspend: myEvent
+    - form: myForm
+      fields:
+        - age: {type: int, value: "${age}"}
+forms:
+  myForm:
+    - name: {type: string, allow: ["${a}", "${b}"], inputType: text}
+"#;
+
+    #[derive(Default)]
+    struct Collector<'a> {
+        strings: Vec<&'a str>,
+        names: Vec<&'a str>,
+        bodies: Vec<&'a str>,
+    }
+
+    impl<'a> Visitor<'a> for Collector<'a> {
+        fn visit_value(&mut self, value: &'a Value) {
+            if let Value::String(s) = value {
+                self.strings.push(s);
+            }
+            walk_value(self, value);
+        }
+
+        fn visit_name(&mut self, name: &'a str) {
+            self.names.push(name);
+        }
+
+        fn visit_script_body(&mut self, body: &'a str) {
+            self.bodies.push(body);
+        }
+    }
+
+    let document = parse_stream(&mut Input::try_from(src).unwrap())
+        .unwrap()
+        .remove(0);
+    let mut collector = Collector::default();
+    collector.visit_document(&document);
+    assert_eq!(collector.strings, ["${age}", "${a}", "${b}", "text"]);
+    assert_eq!(collector.names, ["js", "myEvent", "myForm"]);
+    assert_eq!(collector.bodies, ["println('${x}')"]);
+}
+
+#[test]
+fn visitor_mut() {
+    struct Rename;
+
+    impl VisitorMut for Rename {
+        fn visit_value_mut(&mut self, value: &mut Value) {
+            if let Value::String(s) = value {
+                *s = s.replace("${name}", "${userName}");
+            }
+            walk_value_mut(self, value);
+        }
+
+        fn visit_expression_mut(&mut self, expression: &mut String) {
+            *expression = expression.replace("${name", "${userName");
+        }
+    }
+
+    let mut document = parse();
+    Rename.visit_document_mut(&mut document);
+    let emitted = emit_document(&document);
+    assert!(!emitted.contains("${name"), "{emitted}");
+    assert_eq!(emitted.matches("${userName}").count(), 3, "{emitted}");
+    assert!(emitted.contains("- if: \"${userName != null}\""), "{emitted}");
+    assert!(emitted.contains("- expr: \"${userName.trim()}\""), "{emitted}");
+}